@@ -90,9 +90,9 @@ async fn main(_spawner: Spawner) {
     let mut my_si7021 = Si7021::new(i2c0, embassy_time::Delay);
     my_si7021.init_device().await.unwrap();
 
-    info!("Si70xx read device ID = {:#?} and firmware version = {}, serial_a = {:#08x}, serial_b = {:#08x}",
+    info!("Si70xx read device ID = {:#?} and firmware version = {:?}, serial = {}",
     my_si7021.device_data.id, my_si7021.device_data.firmware_version, 
-    my_si7021.device_data.serial_a, my_si7021.device_data.serial_b);
+    my_si7021.device_data.serial);
 
     let humidity: f32 = my_si7021.read_relative_humidity().await.unwrap();
     info!("humidity is {}", humidity);
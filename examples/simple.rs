@@ -63,9 +63,9 @@ fn main() -> Result<()> {
     //my_si7021.reset_device().unwrap();  ... init will call reset_device()
     my_si7021.init_device().unwrap();
 
-    info!("Si70xx read device ID = {:#?} and firmware version = {}, serial_a = {:#08x}, serial_b = {:#08x}",
+    info!("Si70xx read device ID = {:#?} and firmware version = {:?}, serial = {}",
         my_si7021.device_data.id, my_si7021.device_data.firmware_version, 
-        my_si7021.device_data.serial_a, my_si7021.device_data.serial_b);
+        my_si7021.device_data.serial);
 
     let humidity: f32 = my_si7021.read_relative_humidity().unwrap();
     info!("humidity is {}", humidity);
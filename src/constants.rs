@@ -15,26 +15,32 @@ pub const Si7021_READ_ID_BYTE_1: [u8; 2] = [0xFA, 0x0F];  // Read Electronic ID
 pub const Si7021_READ_ID_BYTE_2: [u8; 2] = [0xFC, 0xC9];  // Read Electronic ID 2nd Byte
 pub const Si7021_READ_FW_VERSION: [u8; 2] = [0x84, 0xB8];  // Read Firmware Revision
 
+// Si7013 only
+pub const Si7013_MEASURE_ANALOG: u8 = 0xEE;  // Measure Analog Voltage or Thermistor Temperature
+pub const Si7013_WRITE_USER_REG_2: u8 = 0x50;  // Write Voltage Measurement Setup (User Register 2)
+pub const Si7013_READ_USER_REG_2: u8 = 0x10;  // Read Voltage Measurement Setup (User Register 2)
+pub const Si7013_WRITE_THERMISTOR_COEFF: u8 = 0xC5;  // Write Thermistor Correction Coefficient
+pub const Si7013_READ_THERMISTOR_COEFF: u8 = 0x84;  // Read Thermistor Correction Coefficient
+
 
 
 #[repr(u8)]
 /// Si7021 I2C device address
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DeviceAddress {
-    /// it only has one I2C address but let's call it primary in case future secondary is needed
+    /// 0x40, Si7006/13/20/21 (Si7013 AD0 pin low)
+    #[default]
     Primary = 0x40,  
+    /// 0x41, Si7013 only with AD0 pin high
+    Secondary = 0x41,
 }
 
 impl From<DeviceAddress> for u8 {
     fn from(value: DeviceAddress) -> Self {
         match value {
             DeviceAddress::Primary => 0x40,
+            DeviceAddress::Secondary => 0x41,
         }
     }
 }
-
-impl Default for DeviceAddress {
-    fn default() -> Self {
-        Self::Primary
-    }
-}
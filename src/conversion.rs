@@ -0,0 +1,71 @@
+// Si70xx datasheet conversion between 16-bit measurement codes and physical values,
+// the f32 functions need feature "float" (default), the integer ones never use floating point
+
+/// relative humidity in percent from a 16-bit RH code, not clamped: values slightly below 0%
+/// or above 100% are possible and the datasheet recommends clamping them
+#[cfg(feature = "float")]
+pub fn rh_from_code(humidity_u16: u16) -> f32 {
+    ( humidity_u16 as f32 * 125.0 / 65536.0 ) - 6.0
+}
+
+/// 16-bit RH code for a relative humidity in percent, saturates at the code range
+#[cfg(feature = "float")]
+pub fn code_from_rh(humidity: f32) -> u16 {
+    ( (humidity + 6.0) * 65536.0 / 125.0 + 0.5 ) as u16
+}
+
+/// temperature in degrees C from a 16-bit temperature code
+#[cfg(feature = "float")]
+pub fn temperature_from_code(temperature_u16: u16) -> f32 {
+    ( temperature_u16 as f32 * 175.72 / 65536.0 ) - 46.85
+}
+
+/// 16-bit temperature code for a temperature in degrees C, saturates at the code range
+#[cfg(feature = "float")]
+pub fn code_from_temperature(temperature: f32) -> u16 {
+    ( (temperature + 46.85) * 65536.0 / 175.72 + 0.5 ) as u16
+}
+
+/// relative humidity in milli-percent (1/1000 %) from a 16-bit RH code, clamped to 0..=100000
+pub fn milli_percent_rh_from_code(humidity_u16: u16) -> u32 {
+    let milli_percent: i64 = ((humidity_u16 as i64 * 125_000) >> 16) - 6_000;
+    milli_percent.clamp(0, 100_000) as u32
+}
+
+/// temperature in centi-degrees C (1/100 degree) from a 16-bit temperature code
+pub fn centi_celsius_from_code(temperature_u16: u16) -> i32 {
+    ((temperature_u16 as i32 * 17_572) >> 16) - 4_685
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_conversion_code_range() {
+        // -6 %RH and 119 %RH are clamped
+        assert_eq!(milli_percent_rh_from_code(0x0000), 0);
+        assert_eq!(milli_percent_rh_from_code(0xffff), 100_000);
+        assert_eq!(milli_percent_rh_from_code(0x72b0), 49_999);
+        assert_eq!(centi_celsius_from_code(0x0000), -4_685);
+        assert_eq!(centi_celsius_from_code(0xffff), 12_886);
+        assert_eq!(centi_celsius_from_code(0x68ad), 2_500);
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn float_conversion_code_range() {
+        assert_eq!(rh_from_code(0x0000), -6.0);
+        assert!((rh_from_code(0xffff) - 118.998).abs() < 0.001);
+        assert_eq!(temperature_from_code(0x0000), -46.85);
+        assert!((temperature_from_code(0xffff) - 128.867).abs() < 0.001);
+
+        assert_eq!(code_from_rh(-6.0), 0x0000);
+        assert_eq!(code_from_rh(rh_from_code(0xffff)), 0xffff);
+        assert_eq!(code_from_temperature(-46.85), 0x0000);
+        assert_eq!(code_from_temperature(temperature_from_code(0xffff)), 0xffff);
+        // saturates outside the code range
+        assert_eq!(code_from_rh(-10.0), 0x0000);
+        assert_eq!(code_from_temperature(200.0), 0xffff);
+    }
+}
@@ -0,0 +1,41 @@
+// Si70xx checksum, CRC-8 with polynomial 0x31 (x^8 + x^5 + x^4 + 1), initial value 0x00
+
+const CRC8_POLYNOMIAL: u8 = 0x31;
+
+/// feed one more byte into a running Si70xx CRC-8
+pub(crate) fn crc8_update(crc: u8, byte: u8) -> u8 {
+    let mut crc = crc ^ byte;
+    for _ in 0..8 {
+        if (crc & 0x80) != 0 {
+            crc = (crc << 1) ^ CRC8_POLYNOMIAL;
+        } else {
+            crc <<= 1;
+        }
+    }
+    crc
+}
+
+/// Si70xx CRC-8 over all bytes of data
+pub(crate) fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0x00, |crc, byte| crc8_update(crc, *byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc8_known_vectors() {
+        // examples from the Sensirion SHT2x CRC checksum application note, same CRC-8 as the Si70xx
+        assert_eq!(crc8(&[0xdc]), 0x79);
+        assert_eq!(crc8(&[0x68, 0x3a]), 0x7c);
+        assert_eq!(crc8(&[0x4e, 0x85]), 0x6b);
+        assert_eq!(crc8(&[]), 0x00);
+    }
+
+    #[test]
+    fn crc8_update_is_cumulative() {
+        let crc = [0x68, 0x3a].iter().fold(0x00, |crc, byte| crc8_update(crc, *byte));
+        assert_eq!(crc, crc8(&[0x68, 0x3a]));
+    }
+}
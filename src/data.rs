@@ -1,71 +1,667 @@
 // no_std support
-#[allow(unused_imports)]
-#[allow(dead_code)]
 // use libm::{exp, round, trunc};
-use log::debug;
 
-#[allow(unused_imports)] // for no_std use
+//#[allow(unused_imports)] // for no_std use
 //use num_traits::float::FloatCore;
 
 //use crate::error::Ens160Error;
 // use bitfield::bitfield;
 
+use crate::constants::DeviceAddress;
 
 /// A measurement result from the sensor.
+#[cfg(feature = "float")]
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Measurements {
     /// temperature degrees C
     pub temperature_c: f32,
     /// relative humidity percent
     pub relative_humidity_percent: f32,
+    /// VDD was below the level where measurements are reliable (User Register 1 VDDS bit)
+    pub vdd_low: bool,
+}
+
+/// relative humidity reading with the unclamped value, saturated readings (condensation or
+/// below 0%) are flagged instead of silently clamped
+#[cfg(feature = "float")]
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HumidityReading {
+    /// relative humidity percent, not clamped to 0..=100
+    pub value: f32,
+    /// 16-bit RH code
+    pub raw: u16,
+    /// value is above 100%
+    pub saturated_high: bool,
+    /// value is below 0%
+    pub saturated_low: bool,
+}
+
+#[cfg(feature = "float")]
+impl HumidityReading {
+    /// reading from a 16-bit RH code
+    pub fn from_code(raw: u16) -> Self {
+        let value = crate::conversion::rh_from_code(raw);
+        HumidityReading {
+            value,
+            raw,
+            saturated_high: value > 100.0,
+            saturated_low: value < 0.0,
+        }
+    }
+
+    /// relative humidity percent clamped to 0..=100 as the datasheet recommends
+    pub fn clamped(self) -> f32 {
+        self.value.clamp(0.0, 100.0)
+    }
+
+    /// value was clamped by clamped()
+    pub fn is_clipped(self) -> bool {
+        self.saturated_high || self.saturated_low
+    }
+}
+
+/// A measurement result from the sensor computed with integer arithmetic only
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntegerMeasurements {
+    /// temperature centi-degrees C (2345 = 23.45 C)
+    pub temperature_centi_c: i32,
+    /// relative humidity milli-percent (45678 = 45.678 %)
+    pub relative_humidity_milli_percent: u32,
+    /// VDD was below the level where measurements are reliable (User Register 1 VDDS bit)
+    pub vdd_low: bool,
 }
 
 /// Si7021 device id and firmware version
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeviceData {
     /// device ID
     pub id: DeviceModel, 
-    pub firmware_version: u8,
-    pub serial_a: u32,
-    pub serial_b: u32,
+    pub firmware_version: FirmwareRevision,
+    /// 64-bit electronic serial number
+    pub serial: SerialNumber,
+}
+
+/// device found by detect(): model, firmware version and serial numbers read from the device and the
+/// sensor variant
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DetectedDevice {
+    /// device ID, NotRead for HTU21D/SHT21 compatible sensors
+    pub id: DeviceModel,
+    pub firmware_version: FirmwareRevision,
+    /// 64-bit electronic serial number, the bytes in Si70xx order for HTU21D/SHT21 compatible sensors
+    pub serial: SerialNumber,
+    /// Si70xx or HTU21D/SHT21 compatible sensor
+    pub variant: SensorVariant,
 }
 
 impl Default for DeviceData {
     fn default() -> Self {
         DeviceData {
             id: DeviceModel::NotRead,
-            firmware_version: 0,
-            serial_a: 0,
-            serial_b: 0,
+            firmware_version: FirmwareRevision::NotRead,
+            serial: SerialNumber::default(),
         }
     }
 }
 
-/// SiLabs device model
+/// Si70xx firmware revision, match on it for revision specific handling. The driver itself uses the same
+/// command timing for all revisions
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FirmwareRevision {
+    /// firmware 1.0, revision byte 0xff
+    V1_0,
+    /// firmware 2.0, revision byte 0x20
+    V2_0,
+    /// revision byte not known to this driver
+    Unknown(u8),
+    #[default]
+    NotRead,
+}
+
+impl From<u8> for FirmwareRevision {
+    fn from(v: u8) -> Self {
+        match v {
+            0xff => Self::V1_0,
+            0x20 => Self::V2_0,
+            _ => Self::Unknown(v),
+        }
+    }
+}
+
+/// 64-bit electronic serial number, SNA_3..SNA_0 followed by SNB_3..SNB_0.
+/// Display is 16 hex digits, parse from hex with or without 0x prefix
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SerialNumber(u64);
+
+impl SerialNumber {
+    /// serial number from the first (SNA) and second (SNB) electronic ID words
+    pub fn new(sna: u32, snb: u32) -> Self {
+        Self(((sna as u64) << 32) | (snb as u64))
+    }
+
+    /// SNA_3..SNA_0, read with the 1st electronic ID access
+    pub fn sna(self) -> u32 {
+        (self.0 >> 32) as u32
+    }
+
+    /// SNB_3..SNB_0, read with the 2nd electronic ID access, SNB_3 is the device ID byte
+    pub fn snb(self) -> u32 {
+        self.0 as u32
+    }
+
+    /// serial number as u64
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for SerialNumber {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<SerialNumber> for u64 {
+    fn from(serial: SerialNumber) -> Self {
+        serial.0
+    }
+}
+
+impl core::fmt::Display for SerialNumber {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:016X}", self.0)
+    }
+}
+
+impl core::str::FromStr for SerialNumber {
+    type Err = core::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+        u64::from_str_radix(digits, 16).map(Self)
+    }
+}
+
+/// SiLabs device model, electronic ID byte SNB_3
 #[derive(Debug, PartialEq, Clone, Copy)]
-#[repr(u8)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DeviceModel {
-    SiEngineeringSample = 0xff,
-    Si7013 = 0x0d,
-    Si7020 = 0x14,
-    Si7021 = 0x15,
-    NotRead = 0x00,
+    /// engineering sample, ID byte 0x00 or 0xff
+    SiEngineeringSample,
+    Si7006,
+    Si7013,
+    Si7020,
+    Si7021,
+    /// ID byte not known to this driver (clone or corrupted read)
+    Unknown(u8),
+    NotRead,
 }
 
-impl From<u8> for DeviceModel {
-    fn from(v: u8) -> Self {
+impl DeviceModel {
+    /// can the device model use this I2C address, only the Si7013 has the secondary address.
+    /// Models not known to this driver are assumed to support it
+    pub fn supports_address(self, address: DeviceAddress) -> bool {
+        match address {
+            DeviceAddress::Primary => true,
+            DeviceAddress::Secondary => !matches!(self, Self::Si7006 | Self::Si7020 | Self::Si7021),
+        }
+    }
+}
+
+impl TryFrom<u8> for DeviceModel {
+    /// the unknown ID byte
+    type Error = u8;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
         match v {
-            0xff => Self::SiEngineeringSample,
-            0x0d => Self::Si7013,
-            0x14 => Self::Si7020,
-            0x15 => Self::Si7021,
-            0x00 => Self::NotRead,
-            _ => unreachable!(),
+            0x00 | 0xff => Ok(Self::SiEngineeringSample),
+            0x06 => Ok(Self::Si7006),
+            0x0d => Ok(Self::Si7013),
+            0x14 => Ok(Self::Si7020),
+            0x15 => Ok(Self::Si7021),
+            _ => Err(v),
+        }
+    }
+}
+
+/// sensor family detected by init_device()/detect()
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SensorVariant {
+    /// Silicon Labs Si7006/13/20/21 with electronic ID, firmware revision, heater control register
+    /// and Read Temperature Value from Previous RH Measurement
+    #[default]
+    Si70xx,
+    /// HTU21D or SHT21: same address and E3/E5/F3/F5 measurement commands, an electronic ID without a Si70xx
+    /// device ID byte, no firmware revision, no heater control register and no temperature from previous
+    /// RH measurement.
+    /// Conversions are slower than on the Si70xx, the driver uses the SHT21 conversion times
+    Sht21Compatible,
+}
+
+impl SensorVariant {
+    /// datasheet maximum temperature conversion time in microseconds at resolution
+    pub fn temperature_conversion_time_us(self, resolution: Resolution) -> u32 {
+        match self {
+            Self::Si70xx => resolution.temperature_conversion_time_us(),
+            // SHT21 datasheet maximum, the HTU21D is faster
+            Self::Sht21Compatible => match resolution {
+                Resolution::Rh12Temp14 => 85_000,
+                Resolution::Rh8Temp12 => 22_000,
+                Resolution::Rh10Temp13 => 43_000,
+                Resolution::Rh11Temp11 => 11_000,
+            },
+        }
+    }
+
+    /// datasheet maximum RH conversion time in microseconds at resolution, for the Si70xx including
+    /// the temperature conversion it does along with an RH measurement
+    pub fn humidity_conversion_time_us(self, resolution: Resolution) -> u32 {
+        match self {
+            Self::Si70xx => resolution.humidity_conversion_time_us(),
+            // SHT21 datasheet maximum, the HTU21D is faster
+            Self::Sht21Compatible => match resolution {
+                Resolution::Rh12Temp14 => 29_000,
+                Resolution::Rh8Temp12 => 4_000,
+                Resolution::Rh10Temp13 => 9_000,
+                Resolution::Rh11Temp11 => 15_000,
+            },
+        }
+    }
+
+    /// default No Hold Master Mode polling deadline in microseconds, above the longest conversion
+    pub fn default_measurement_timeout_us(self) -> u32 {
+        match self {
+            Self::Si70xx => 50_000,
+            Self::Sht21Compatible => 100_000,
         }
     }
 }
 
+/// RH and temperature measurement resolution, RES1 (bit 7) and RES0 (bit 0) of User Register 1
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Resolution {
+    /// 12 bit RH, 14 bit temperature (power-on default)
+    #[default]
+    Rh12Temp14,
+    /// 8 bit RH, 12 bit temperature
+    Rh8Temp12,
+    /// 10 bit RH, 13 bit temperature
+    Rh10Temp13,
+    /// 11 bit RH, 11 bit temperature
+    Rh11Temp11,
+}
 
+impl Resolution {
+    /// User Register 1 bits RES1 and RES0 mask
+    pub const MASK: u8 = 0x81;
 
+    /// get resolution from User Register 1 value (other bits are ignored)
+    pub fn from_register(value: u8) -> Self {
+        match value & Self::MASK {
+            0x00 => Self::Rh12Temp14,
+            0x01 => Self::Rh8Temp12,
+            0x80 => Self::Rh10Temp13,
+            _ => Self::Rh11Temp11,
+        }
+    }
 
+    /// RES1 and RES0 bits for User Register 1
+    pub fn register_bits(self) -> u8 {
+        match self {
+            Self::Rh12Temp14 => 0x00,
+            Self::Rh8Temp12 => 0x01,
+            Self::Rh10Temp13 => 0x80,
+            Self::Rh11Temp11 => 0x81,
+        }
+    }
+
+    /// datasheet maximum temperature conversion time in microseconds
+    pub fn temperature_conversion_time_us(self) -> u32 {
+        match self {
+            Self::Rh12Temp14 => 10_800,
+            Self::Rh8Temp12 => 3_800,
+            Self::Rh10Temp13 => 6_200,
+            Self::Rh11Temp11 => 2_400,
+        }
+    }
 
+    /// datasheet maximum RH conversion time in microseconds, this includes the temperature
+    /// conversion the Si70xx always does along with an RH measurement
+    pub fn humidity_conversion_time_us(self) -> u32 {
+        let rh_conversion_us = match self {
+            Self::Rh12Temp14 => 12_000,
+            Self::Rh8Temp12 => 3_100,
+            Self::Rh10Temp13 => 4_500,
+            Self::Rh11Temp11 => 7_000,
+        };
+        rh_conversion_us + self.temperature_conversion_time_us()
+    }
+}
+
+/// RH/T User Register 1
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UserRegister1 {
+    /// RES1 and RES0 measurement resolution bits
+    pub resolution: Resolution,
+    /// VDDS bit, VDD is low (read only, ignored when written)
+    pub vdd_low: bool,
+    /// HTRE bit, on-chip heater enabled
+    pub heater_enabled: bool,
+    // reserved bits, written back as read
+    reserved: u8,
+}
+
+impl UserRegister1 {
+    /// power-on reset value
+    pub const RESET_VALUE: u8 = 0x3a;
+    const VDDS: u8 = 0x40;
+    const HTRE: u8 = 0x04;
+    const RESERVED_MASK: u8 = 0x3a;
+}
+
+impl Default for UserRegister1 {
+    fn default() -> Self {
+        Self::from(Self::RESET_VALUE)
+    }
+}
+
+impl From<u8> for UserRegister1 {
+    fn from(value: u8) -> Self {
+        UserRegister1 {
+            resolution: Resolution::from_register(value),
+            vdd_low: (value & Self::VDDS) != 0x00,
+            heater_enabled: (value & Self::HTRE) != 0x00,
+            reserved: value & Self::RESERVED_MASK,
+        }
+    }
+}
+
+impl From<UserRegister1> for u8 {
+    fn from(register: UserRegister1) -> Self {
+        let mut value = register.reserved | register.resolution.register_bits();
+        if register.vdd_low {
+            value |= UserRegister1::VDDS;
+        }
+        if register.heater_enabled {
+            value |= UserRegister1::HTRE;
+        }
+        value
+    }
+}
+
+/// how the driver waits for a measurement conversion
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MeasurementMode {
+    /// No Hold Master Mode: send the command, wait the conversion time then read the result
+    #[default]
+    NoHold,
+    /// Hold Master Mode: the Si70xx stretches the I2C clock until the result is ready, so a
+    /// measurement is a single write_read. Only use if the I2C controller supports clock stretching
+    Hold,
+}
+
+/// heater power level, the 4-bit value of the Heater Control Register
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HeaterLevel(u8);
+
+impl HeaterLevel {
+    /// Heater Control Register HEATER[3:0] bits mask
+    pub const MASK: u8 = 0x0f;
+
+    // datasheet typical heater current in uA at VDD = 3.3V, levels not in the datasheet table are interpolated
+    const TYPICAL_CURRENT_UA: [u32; 16] = [
+        3_090, 9_180, 15_240, 21_310, 27_390, 33_470, 39_550, 45_620,
+        51_690, 57_760, 63_830, 69_910, 75_980, 82_050, 88_130, 94_200,
+    ];
+
+    /// heater level 0x00 to 0x0f, None if out of range
+    pub fn new(level: u8) -> Option<Self> {
+        if level > Self::MASK {
+            return None;
+        }
+        Some(Self(level))
+    }
+
+    /// get heater level from Heater Control Register value (reserved bits are ignored)
+    pub fn from_register(value: u8) -> Self {
+        Self(value & Self::MASK)
+    }
+
+    /// 4-bit heater level value
+    pub fn level(self) -> u8 {
+        self.0
+    }
+
+    /// datasheet typical heater current in uA (VDD = 3.3V), integer arithmetic only
+    pub fn typical_current_ua(self) -> u32 {
+        Self::TYPICAL_CURRENT_UA[self.0 as usize]
+    }
+
+    /// datasheet typical heater current in mA (VDD = 3.3V)
+    #[cfg(feature = "float")]
+    pub fn typical_current_ma(self) -> f32 {
+        self.typical_current_ua() as f32 / 1000.0
+    }
+}
+
+/// Si7013 analog input voltage reference (User Register 2 VREFP bit)
+#[cfg(feature = "si7013")]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum VoltageReference {
+    /// internal 1.25V reference
+    #[default]
+    Internal1V25,
+    /// VDDA supply, for ratiometric thermistor measurements
+    Vdda,
+}
+
+/// Si7013 Voltage Measurement Setup, User Register 2
+#[cfg(feature = "si7013")]
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UserRegister2 {
+    /// VREFP bit, analog input voltage reference
+    pub reference: VoltageReference,
+    /// VOUT bit, drive the VOUT pin (thermistor bias) to VDD instead of GND
+    pub vout_high: bool,
+    /// VIN_BUF bit, buffer the VIN input (for high impedance sources)
+    pub vin_buffered: bool,
+    /// THERM_CORR bit, apply the thermistor correction coefficient table to analog measurements
+    pub thermistor_correction: bool,
+    /// NO_HOLD bit, Measure Analog Voltage (0xEE) runs in No Hold Master Mode instead of stretching the clock
+    pub no_hold: bool,
+    // reserved bits, written back as read
+    reserved: u8,
+}
+
+// bit positions from the User Register 2 table of the Si7013-A20 datasheet:
+// D7 reserved, D6 NO_HOLD, D5 THERM_CORR, D4 and D3 reserved, D2 VIN_BUF, D1 VREFP, D0 VOUT
+#[cfg(feature = "si7013")]
+impl UserRegister2 {
+    const NO_HOLD: u8 = 0x40;
+    const THERM_CORR: u8 = 0x20;
+    const VIN_BUF: u8 = 0x04;
+    const VREFP: u8 = 0x02;
+    const VOUT: u8 = 0x01;
+    const RESERVED_MASK: u8 = 0x98;
+}
+
+#[cfg(feature = "si7013")]
+impl From<u8> for UserRegister2 {
+    fn from(value: u8) -> Self {
+        UserRegister2 {
+            reference: if (value & Self::VREFP) != 0x00 { VoltageReference::Vdda } else { VoltageReference::Internal1V25 },
+            vout_high: (value & Self::VOUT) != 0x00,
+            vin_buffered: (value & Self::VIN_BUF) != 0x00,
+            thermistor_correction: (value & Self::THERM_CORR) != 0x00,
+            no_hold: (value & Self::NO_HOLD) != 0x00,
+            reserved: value & Self::RESERVED_MASK,
+        }
+    }
+}
+
+#[cfg(feature = "si7013")]
+impl From<UserRegister2> for u8 {
+    fn from(register: UserRegister2) -> Self {
+        let mut value = register.reserved;
+        if register.reference == VoltageReference::Vdda {
+            value |= UserRegister2::VREFP;
+        }
+        if register.vout_high {
+            value |= UserRegister2::VOUT;
+        }
+        if register.vin_buffered {
+            value |= UserRegister2::VIN_BUF;
+        }
+        if register.thermistor_correction {
+            value |= UserRegister2::THERM_CORR;
+        }
+        if register.no_hold {
+            value |= UserRegister2::NO_HOLD;
+        }
+        value
+    }
+}
+
+/// Si7013 external thermistor setup: correction coefficient table and analog input configuration
+#[cfg(feature = "si7013")]
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ThermistorConfig<'a> {
+    /// (coefficient address, value) pairs written with Write Thermistor Correction Coefficient, the table
+    /// maps the analog input code to a temperature code (see Si7013 datasheet and AN607)
+    pub coefficients: &'a [(u8, u8)],
+    /// analog input voltage reference, usually VDDA for a thermistor divider
+    pub reference: VoltageReference,
+    /// buffer the VIN input
+    pub vin_buffered: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolution_register_bits() {
+        let resolutions = [
+            (0x00, Resolution::Rh12Temp14),
+            (0x01, Resolution::Rh8Temp12),
+            (0x80, Resolution::Rh10Temp13),
+            (0x81, Resolution::Rh11Temp11),
+        ];
+        for (bits, resolution) in resolutions {
+            assert_eq!(Resolution::from_register(bits), resolution);
+            assert_eq!(Resolution::from_register(bits | 0x7e), resolution);
+            assert_eq!(resolution.register_bits(), bits);
+        }
+    }
+
+    #[test]
+    fn user_register_1_keeps_reserved_bits() {
+        for resolution in [Resolution::Rh12Temp14, Resolution::Rh8Temp12, Resolution::Rh10Temp13, Resolution::Rh11Temp11] {
+            let mut register = UserRegister1::from(UserRegister1::RESET_VALUE);
+            register.resolution = resolution;
+            assert_eq!(u8::from(register), UserRegister1::RESET_VALUE | resolution.register_bits());
+
+            register.heater_enabled = true;
+            assert_eq!(u8::from(register), UserRegister1::RESET_VALUE | resolution.register_bits() | 0x04);
+        }
+        let register = UserRegister1::from(0xff);
+        assert_eq!(register.resolution, Resolution::Rh11Temp11);
+        assert!(register.vdd_low);
+        assert!(register.heater_enabled);
+        assert_eq!(u8::from(register), 0xff);
+        assert_eq!(UserRegister1::default(), UserRegister1::from(0x3a));
+    }
+
+    #[test]
+    fn serial_number_from_str() {
+        let serial = SerialNumber::new(0x1234_5678, 0x1500_9abc);
+        assert_eq!("1234567815009ABC".parse::<SerialNumber>(), Ok(serial));
+        assert_eq!("0x1234567815009abc".parse::<SerialNumber>(), Ok(serial));
+        assert_eq!("0X123456781500ABC".parse::<SerialNumber>(), Ok(SerialNumber::from(0x0123_4567_8150_0abc)));
+        assert!("".parse::<SerialNumber>().is_err());
+        assert!("0x".parse::<SerialNumber>().is_err());
+        assert!("12345678G".parse::<SerialNumber>().is_err());
+        assert!("123456781500ABCDE".parse::<SerialNumber>().is_err());
+        assert_eq!((serial.sna(), serial.snb()), (0x1234_5678, 0x1500_9abc));
+    }
+
+    #[test]
+    fn serial_number_display_parses_back() {
+        extern crate std;
+        use std::string::ToString;
+
+        let serial = SerialNumber::from(0x0012_3456_789a_bcde);
+        assert_eq!(serial.to_string(), "00123456789ABCDE");
+        assert_eq!(serial.to_string().parse::<SerialNumber>(), Ok(serial));
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn humidity_reading_saturation() {
+        let low = HumidityReading::from_code(0x0000);
+        assert_eq!(low.value, -6.0);
+        assert!(low.saturated_low && !low.saturated_high);
+        assert!(low.is_clipped());
+        assert_eq!(low.clamped(), 0.0);
+
+        let high = HumidityReading::from_code(0xffff);
+        assert!((high.value - 118.998).abs() < 0.001);
+        assert!(high.saturated_high && !high.saturated_low);
+        assert!(high.is_clipped());
+        assert_eq!(high.clamped(), 100.0);
+
+        let reading = HumidityReading::from_code(0x72b0);
+        assert_eq!(reading.raw, 0x72b0);
+        assert!(!reading.saturated_high && !reading.saturated_low);
+        assert!(!reading.is_clipped());
+        assert_eq!(reading.clamped(), reading.value);
+        assert!((reading.value - 50.0).abs() < 0.001);
+    }
+
+    #[cfg(feature = "si7013")]
+    #[test]
+    fn user_register_2_bits() {
+        let register = UserRegister2::from(0x00);
+        let set = |modify: fn(&mut UserRegister2)| {
+            let mut register = register;
+            modify(&mut register);
+            u8::from(register)
+        };
+        assert_eq!(set(|r| r.vout_high = true), 0x01);
+        assert_eq!(set(|r| r.reference = VoltageReference::Vdda), 0x02);
+        assert_eq!(set(|r| r.vin_buffered = true), 0x04);
+        assert_eq!(set(|r| r.thermistor_correction = true), 0x20);
+        assert_eq!(set(|r| r.no_hold = true), 0x40);
+
+        // reserved bits D7, D4 and D3 are written back as read
+        let mut register = UserRegister2::from(0x98);
+        assert_eq!(register, UserRegister2::from(0x98));
+        assert!(!register.vout_high && !register.vin_buffered && !register.thermistor_correction && !register.no_hold);
+        assert_eq!(register.reference, VoltageReference::Internal1V25);
+        register.thermistor_correction = true;
+        assert_eq!(u8::from(register), 0xb8);
+        assert_eq!(u8::from(UserRegister2::from(0xff)), 0xff);
+    }
+}
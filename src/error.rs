@@ -1,77 +1,113 @@
-// use core::fmt::Formatter;
+use core::fmt::{Debug, Display, Formatter};
 
-// use embedded_hal::i2c::{I2c, SevenBitAddress};
-// use embedded_hal::i2c::{Error as I2cError, ErrorKind as I2cErrorKind};
+use embedded_hal::i2c::{Error as I2cError, ErrorKind as I2cErrorKind};
 
 /// All possible errors
-/// Display not implemented for no_std support
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Error<E> {
+    /// device did not acknowledge its I2C address during detection (is_connected, reset_device, init_device),
+    /// NACKs with unknown source count as address NACKs
     NotConnected,
+    /// device did not acknowledge its I2C address during a command (also NoAcknowledge with unknown source)
+    AddressNack { address: u8, command: u8 },
     /// parameter out of range
     OutOfRange(u8),
-    /// measurement timeout
-    MeasurementTimeout(),
+    /// No Hold Master Mode measurement command still not done after waited_us microseconds
+    MeasurementTimeout { command: u8, waited_us: u32 },
+    /// checksum of data read for command does not match the computed CRC-8
+    CrcMismatch { command: u8, expected: u8, actual: u8 },
+    /// electronic ID byte is not a known Si70xx device model
+    UnexpectedDeviceId(u8),
+    /// the detected device model does not support this I2C address
+    AddressNotSupported(u8),
+    /// command is not supported by the detected sensor variant or device model
+    Unsupported(u8),
+    /// Si7013 command (feature "si7013") before init_device() or detect() read the device model, the other
+    /// commands work without detection and never return it
+    NotInitialized,
+    /// Si7013 thermistor temperature read with thermistor correction off, call configure_thermistor() first
+    ThermistorNotConfigured,
+    /// try_fetch_*() without a measurement started by start_*_measurement()
+    MeasurementNotStarted,
+    /// try_fetch_*() of another measurement than the started one (No Hold Master Mode commands)
+    MeasurementMismatch { started: u8, requested: u8 },
     /// An error in the  underlying I²C system
     I2c(E),
 }
 
-// impl<E> From<E> for Error<E>
-// where
-//     E: I2cError,
-// {
-//     fn from(error: E) -> Self {
-//         Self::I2c(error.kind())
-//     }
-// }
-
+impl<E> From<E> for Error<E> {
+    fn from(error: E) -> Self {
+        Self::I2c(error)
+    }
+}
 
-/****
-pub enum Si7021Error<I2C>
+impl<E> Error<E>
 where
-    I2C: I2c<SevenBitAddress>
+    E: I2cError,
 {
-    /// Error during I2C write operation.
-    WriteError(I2C::Error),
-    /// Error during I2C WriteRead operation.
-    WriteReadError(I2C::Error),
-    /// Got an unexpected Part Id during sensor initalization.
-    UnexpectedChipId(u8),
+    /// embedded-hal ErrorKind of the underlying I²C error, None for driver errors
+    pub fn i2c_kind(&self) -> Option<I2cErrorKind> {
+        match self {
+            Error::I2c(e) => Some(e.kind()),
+            _ => None,
+        }
+    }
 
-    /// sths34pf80 device not connected to I2C bus
-    NotConnected,
-    /// parameter out of range
-    OutOfRange(u8),
-    /// measurement timeout
-    MeasurementTimeout(),
+    /// replace the HAL specific I²C error by its embedded-hal ErrorKind, which implements
+    /// Display and is Send + Sync + 'static so it can be propagated with ? (anyhow, thiserror)
+    pub fn into_kind(self) -> Error<I2cErrorKind> {
+        match self {
+            Error::NotConnected => Error::NotConnected,
+            Error::AddressNack { address, command } => Error::AddressNack { address, command },
+            Error::OutOfRange(value) => Error::OutOfRange(value),
+            Error::MeasurementTimeout { command, waited_us } => Error::MeasurementTimeout { command, waited_us },
+            Error::CrcMismatch { command, expected, actual } => Error::CrcMismatch { command, expected, actual },
+            Error::UnexpectedDeviceId(id) => Error::UnexpectedDeviceId(id),
+            Error::AddressNotSupported(address) => Error::AddressNotSupported(address),
+            Error::Unsupported(command) => Error::Unsupported(command),
+            Error::NotInitialized => Error::NotInitialized,
+            Error::ThermistorNotConfigured => Error::ThermistorNotConfigured,
+            Error::MeasurementNotStarted => Error::MeasurementNotStarted,
+            Error::MeasurementMismatch { started, requested } => Error::MeasurementMismatch { started, requested },
+            Error::I2c(e) => Error::I2c(e.kind()),
+        }
+    }
 }
 
-impl<I2C> core::fmt::Debug for Si7021Error<I2C>
+impl<E> Display for Error<E>
 where
-    I2C: I2c<SevenBitAddress>
+    E: Debug,
 {
-    fn fmt(&self, f: &mut Formatter<'_>) -> core::result::Result<(), core::fmt::Error> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
-            Si7021Error::WriteReadError(e) => f.debug_tuple("WriteReadError").field(e).finish(),
-            Si7021Error::WriteError(e) => f.debug_tuple("WriteError").field(e).finish(),
-            Si7021Error::UnexpectedChipId(chip_id) => f
-                .debug_tuple("Expected part id 0xd3, got : ") // ToDo:  fix this one
-                .field(chip_id)
-                .finish(),
-            Si7021Error::NotConnected => f
-                .debug_tuple("Si7021 series device is not connected to microcontroller")
-                .finish(),
-
-            Si7021Error::OutOfRange(value) => f
-                .debug_tuple("Set value out of range, check Si7021 datasheet")
-                .field(value)
-                .finish(),
-            Si7021Error::MeasurementTimeout() => f
-                .debug_tuple("timeout waiting for new measurement data ready")
-                .finish(),
+            Error::NotConnected => write!(f, "Si70xx device is not connected to the I2C bus"),
+            Error::AddressNack { address, command } => {
+                write!(f, "no ACK from address {:#04x} for command {:#04x}", address, command)
+            }
+            Error::OutOfRange(value) => write!(f, "set value {:#04x} out of range, check Si7021 datasheet", value),
+            Error::MeasurementTimeout { command, waited_us } => {
+                write!(f, "timeout waiting for measurement {:#04x} data ready after {} usec", command, waited_us)
+            }
+            Error::CrcMismatch { command, expected, actual } => {
+                write!(f, "CRC mismatch for command {:#04x}, expected {:#04x} got {:#04x}", command, expected, actual)
+            }
+            Error::UnexpectedDeviceId(id) => write!(f, "unexpected Si70xx device ID {:#04x}", id),
+            Error::AddressNotSupported(address) => {
+                write!(f, "I2C address {:#04x} is not supported by the detected device", address)
+            }
+            Error::Unsupported(command) => {
+                write!(f, "command {:#04x} is not supported by the detected sensor", command)
+            }
+            Error::NotInitialized => write!(f, "device model not read, call init_device() before Si7013 commands"),
+            Error::ThermistorNotConfigured => write!(f, "thermistor correction off, call configure_thermistor() first"),
+            Error::MeasurementNotStarted => write!(f, "no measurement started to fetch"),
+            Error::MeasurementMismatch { started, requested } => {
+                write!(f, "fetch of measurement {:#04x} but {:#04x} was started", requested, started)
+            }
+            Error::I2c(e) => write!(f, "I2C error: {:?}", e),
         }
     }
 }
 
-
-*************/
\ No newline at end of file
+impl<E> core::error::Error for Error<E> where E: Debug {}
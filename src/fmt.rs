@@ -0,0 +1,25 @@
+// logging macros used by the driver, routed to defmt with feature "defmt" and to the log crate otherwise.
+// format strings have to be accepted by both, so only {}, {:?}, {:x} and {:#x} style hints are used
+
+// unused when neither the "sync" nor the "async" driver is built
+#![allow(unused_macros)]
+
+#[cfg(feature = "defmt")]
+macro_rules! debug {
+    ($($arg:tt)*) => { ::defmt::debug!($($arg)*) };
+}
+
+#[cfg(not(feature = "defmt"))]
+macro_rules! debug {
+    ($($arg:tt)*) => { ::log::debug!($($arg)*) };
+}
+
+#[cfg(feature = "defmt")]
+macro_rules! info {
+    ($($arg:tt)*) => { ::defmt::info!($($arg)*) };
+}
+
+#[cfg(not(feature = "defmt"))]
+macro_rules! info {
+    ($($arg:tt)*) => { ::log::info!($($arg)*) };
+}
@@ -3,328 +3,832 @@
 #![allow(unused_variables)]
 
 //#![feature(inherent_associated_types)]
-pub mod error;
+// must come first, the logging macros are only visible to modules declared after it
+#[macro_use]
+mod fmt;
 
-use crate::error::Error;
+pub mod error;
 
 pub mod data;
-use crate::data::DeviceData;
 
 pub mod constants;
 
-use crate::constants::DeviceAddress::Primary;
+pub mod conversion;
 
-use constants::{Si7021_READ_FW_VERSION, Si7021_READ_ID_BYTE_1, Si7021_READ_ID_BYTE_2, Si7021_READ_RH_NO_HOLD, Si7021_READ_RH_T_USER_REG_1, Si7021_READ_TEMP_AFTER_PREVIOUS_RH, Si7021_READ_TEMP_NO_HOLD, Si7021_RESET, Si7021_WRITE_HEATER_CONTROL, Si7021_WRITE_RH_T_USER_REG_1};
-use data::{DeviceModel, Measurements};
+mod crc;
 
-#[cfg(not(feature = "async"))]
-use embedded_hal::{i2c::I2c, delay::DelayNs};
-#[cfg(feature = "async")]
-use embedded_hal_async::{i2c::I2c as AsyncI2c, delay::DelayNs as AsyncDelayNs};
+#[cfg(feature = "sim")]
+pub mod sim;
 
-use log::{debug, info};
+// both blocking::Si7021 (feature "sync") and asynch::Si7021 (feature "async") can be used in one build,
+// si7021_t_rh::Si7021 stays the async driver when the "async" feature is enabled, as in earlier versions
+#[cfg(feature = "async")]
+pub use crate::asynch::Si7021;
+#[cfg(all(feature = "sync", not(feature = "async")))]
+pub use crate::blocking::Si7021;
+
+/// default interval between No Hold Master Mode read attempts in microseconds
+const DEFAULT_POLL_INTERVAL_US: u32 = 1_000;
+/// conservative wait for a Si7013 No Hold Master Mode analog measurement when polling is disabled
+#[cfg(feature = "si7013")]
+const SI7013_ANALOG_CONVERSION_TIME_US: u32 = 12_000;
+
+// RH in percent clamped to 0..=100 as the datasheet recommends
+#[cfg(feature = "float")]
+fn clamped_rh_from_code(humidity_u16: u16) -> f32 {
+    conversion::rh_from_code(humidity_u16).clamp(0.0, 100.0)
+}
 
 
 
 
 //#[allow(unused_imports)]
 //use embedded_hal::{delay::DelayNs, i2c::I2c, i2c::ErrorType};
-//// use libm::{powf, truncf};
+// use libm::{powf, truncf};
 //use log::{debug, info};
 
 
-/// the Si7021 device
-pub struct Si7021<I2C, D> {
-    /// I²C interface
-    i2c: I2C,
-    /// I²C device address
-    address: u8,
-    delayer: D,
-    pub device_data: DeviceData,
-}
+#[maybe_async_cfg::maybe(
+    sync(
+        feature = "sync",
+        self = "blocking",
+        idents(AsyncI2c(sync = "I2c"), AsyncDelayNs(sync = "DelayNs"))
+    ),
+    async(feature = "async", keep_self)
+)]
+/// Si7021 driver, the async version is generated from this module as is, the blocking version
+/// (module blocking) by removing async/await
+pub mod asynch {
+    use crate::error::Error;
+    use crate::data::{DetectedDevice, DeviceData, DeviceModel, FirmwareRevision, HeaterLevel, IntegerMeasurements, MeasurementMode, Resolution, SensorVariant, SerialNumber, UserRegister1};
+    #[cfg(feature = "float")]
+    use crate::data::{HumidityReading, Measurements};
+    #[cfg(feature = "si7013")]
+    use crate::data::{ThermistorConfig, UserRegister2};
+    #[cfg(feature = "si7013")]
+    use crate::constants::{Si7013_MEASURE_ANALOG, Si7013_READ_THERMISTOR_COEFF, Si7013_READ_USER_REG_2, Si7013_WRITE_THERMISTOR_COEFF, Si7013_WRITE_USER_REG_2};
+    use crate::constants::DeviceAddress;
+    use crate::constants::{Si7021_READ_FW_VERSION, Si7021_READ_HEATER_CONTROL, Si7021_READ_ID_BYTE_1, Si7021_READ_ID_BYTE_2, Si7021_READ_RH_HOLD, Si7021_READ_RH_NO_HOLD, Si7021_READ_RH_T_USER_REG_1, Si7021_READ_TEMP_AFTER_PREVIOUS_RH, Si7021_READ_TEMP_HOLD, Si7021_READ_TEMP_NO_HOLD, Si7021_RESET, Si7021_WRITE_HEATER_CONTROL, Si7021_WRITE_RH_T_USER_REG_1};
+    use crate::crc::{crc8, crc8_update};
+    use crate::conversion::{centi_celsius_from_code, milli_percent_rh_from_code};
+    #[cfg(feature = "float")]
+    use crate::conversion::temperature_from_code;
+    #[cfg(feature = "float")]
+    use crate::clamped_rh_from_code;
+    use crate::DEFAULT_POLL_INTERVAL_US;
+    #[cfg(feature = "si7013")]
+    use crate::SI7013_ANALOG_CONVERSION_TIME_US;
+
+    #[maybe_async_cfg::only_if(sync)]
+    use embedded_hal::{i2c::I2c, delay::DelayNs};
+    #[maybe_async_cfg::only_if(async)]
+    use embedded_hal_async::{i2c::I2c as AsyncI2c, delay::DelayNs as AsyncDelayNs};
+
+    use embedded_hal::i2c::{Error as I2cError, ErrorKind, NoAcknowledgeSource};
+
+    /// the Si7021 device
+    pub struct Si7021<I2C, D> {
+        /// I²C interface
+        i2c: I2C,
+        /// I²C device address
+        address: u8,
+        delayer: D,
+        pub device_data: DeviceData,
+        /// verify the CRC-8 checksum sent with measurements and electronic ID
+        crc_check: bool,
+        /// active measurement resolution, selects the conversion wait time
+        resolution: Resolution,
+        /// Hold or No Hold Master Mode measurements
+        measurement_mode: MeasurementMode,
+        /// No Hold Master Mode read retry interval, 0 = wait the full conversion time instead of polling
+        poll_interval_us: u32,
+        /// No Hold Master Mode polling deadline
+        measurement_timeout_us: Option<u32>,
+        /// Si70xx or HTU21D/SHT21 compatible part, selects the supported commands
+        variant: SensorVariant,
+        /// No Hold Master Mode command sent by start_humidity_measurement()/start_temperature_measurement(),
+        /// None when no measurement is waiting to be fetched
+        pending_command: Option<u8>,
+    }
 
-#[cfg(not(feature = "async"))]
-impl<I2C, D, E> Si7021<I2C, D>
-where  
-    I2C: I2c<Error = E>,
-    D: DelayNs,
-{
-    
-    //type Error = Error;
-    /// create new Si7021 driver with default I2C address: ADDR pin low
-    pub fn new(i2c: I2C, delayer: D) -> Self {
-        log::debug!("new called");
-        Self {
-            i2c,
-            address: Primary.into(),
-            delayer,
-            device_data: DeviceData { ..Default::default()},
+    impl<I2C, D> Si7021<I2C, D> {
+        /// enable/disable CRC-8 checksum verification (enabled by default), disabling it
+        /// saves a little CPU time on buses where speed matters more than detecting corruption
+        pub fn set_crc_check(&mut self, enable: bool) {
+            self.crc_check = enable;
         }
-    }
 
-    /// give back the I2C interface
-    pub fn release(self) -> I2C {
-        self.i2c
-    }
+        /// is CRC-8 checksum verification enabled
+        pub fn is_crc_check_enabled(&self) -> bool {
+            self.crc_check
+        }
 
-}
+        /// measurement resolution the driver last wrote to or read from the device
+        pub fn resolution(&self) -> Resolution {
+            self.resolution
+        }
 
-#[cfg(feature = "async")]
-impl<I2C, D, E> Si7021<I2C, D>
-where  
-    I2C: AsyncI2c<Error = E>,
-    D: AsyncDelayNs,
-{
-    //type Error = Error;
-    /// create new Si7021 driver with default I2C address: ADDR pin low
-    pub fn new(i2c: I2C, delayer: D) -> Self {
-        debug!("new called");
-        Self {
-            i2c,
-            address: Primary.into(),
-            delayer,
-            device_data: DeviceData { ..Default::default()},
+        /// select Hold Master Mode (clock stretching) or No Hold Master Mode (default) measurements
+        pub fn set_measurement_mode(&mut self, mode: MeasurementMode) {
+            self.measurement_mode = mode;
         }
-    }
 
-    /// give back the I2C interface
-    pub fn release(self) -> I2C {
-        self.i2c
-    }
+        /// get the measurement mode
+        pub fn measurement_mode(&self) -> MeasurementMode {
+            self.measurement_mode
+        }
 
-}
+        /// set the interval between No Hold Master Mode read attempts while the Si70xx NACKs its
+        /// address during a conversion (default 1000 usec). 0 disables polling: the driver then waits
+        /// the datasheet maximum conversion time for the active resolution and reads once
+        pub fn set_poll_interval_us(&mut self, interval_us: u32) {
+            self.poll_interval_us = interval_us;
+        }
 
-#[maybe_async_cfg::maybe(
-    sync(
-        cfg(not(feature = "async")),
-        self = "Si7021",
-        idents(AsyncI2c(sync = "I2c"), AsyncDelayNs(sync = "DelayNs"))
-    ),
-    async(feature = "async", keep_self)
-)]
+        /// set how long No Hold Master Mode polling may take before Error::MeasurementTimeout, the default
+        /// depends on the sensor variant (50000 usec for the Si70xx, 100000 usec for HTU21D/SHT21)
+        pub fn set_measurement_timeout_us(&mut self, timeout_us: u32) {
+            self.measurement_timeout_us = Some(timeout_us);
+        }
 
-impl<I2C, D, E> Si7021<I2C, D>
-where  
-    I2C: AsyncI2c<Error = E>,
-    D: AsyncDelayNs,
-{
+        /// No Hold Master Mode polling deadline, set or the default of the sensor variant
+        pub fn measurement_timeout_us(&self) -> u32 {
+            self.measurement_timeout_us.unwrap_or(self.variant.default_measurement_timeout_us())
+        }
 
+        /// sensor variant detected by init_device()/detect()
+        pub fn sensor_variant(&self) -> SensorVariant {
+            self.variant
+        }
 
-    // command_buf is an u8 array that starts with command byte followed by command data byte(s)
-    async fn write_command<const N: usize>(&mut self, command_buf: [u8; N] ) -> Result<(), Error<E>> {
-        // debug!("write_command : {:#?}", command_buf);
-        self.i2c
-            .write(self.address, &command_buf).await
-            .map_err(Error::I2c)?;
-        Ok(())
+        /// set the sensor variant without detection, e.g. for a known HTU21D/SHT21 fleet
+        pub fn set_sensor_variant(&mut self, variant: SensorVariant) {
+            self.variant = variant;
+        }
     }
 
-    async fn read_register( &mut self, register_address: u8, buffer: &mut [u8] ) -> Result<(), Error<E>> {
-        let mut command_buffer = [0u8; 1];
-        command_buffer[0] = register_address;
-        // let mut result_buffer = [0u8; N];
-        self.i2c
-            .write_read(self.address, &command_buffer, buffer).await
-            .map_err(Error::I2c)?;
-        Ok(())
-    }
+    impl<I2C, D, E> Si7021<I2C, D>
+    where  
+        I2C: AsyncI2c<Error = E>,
+        D: AsyncDelayNs,
+    {
+        //type Error = Error;
+        /// create new Si7021 driver with default I2C address: ADDR pin low
+        pub fn new(i2c: I2C, delayer: D) -> Self {
+            Self::new_with_address(i2c, delayer, DeviceAddress::Primary)
+        }
 
+        /// create new Si7021 driver with I2C address, DeviceAddress::Secondary is for Si7013 with AD0 pin high
+        pub fn new_with_address(i2c: I2C, delayer: D, address: DeviceAddress) -> Self {
+            debug!("new called, address {:?}", address);
+            Self {
+                i2c,
+                address: address.into(),
+                delayer,
+                device_data: DeviceData { ..Default::default()},
+                crc_check: true,
+                resolution: Resolution::default(),
+                measurement_mode: MeasurementMode::default(),
+                poll_interval_us: DEFAULT_POLL_INTERVAL_US,
+                measurement_timeout_us: None,
+                variant: SensorVariant::default(),
+                pending_command: None,
+            }
+        }
 
-    /// check if Si7021 is connected by reading Read RH/T User Register 1
-    pub async fn is_connected(&mut self) -> Result<bool, Error<E>> {
-        debug!("in is_connected()");
-        let mut result_buf: [u8; 1] = [0; 1];
-        self.read_register(Si7021_READ_RH_T_USER_REG_1, &mut result_buf).await?;
-        debug!(" is_connected() {:#04x}, expected 0x3a", result_buf[0]);
-        #[allow(unused_parens)]
-        if (result_buf[0] == 0x3a) {  // why 0x3a ?
-            Ok(true)
-        } else {
-            Ok(false)
+        /// give back the I2C interface
+        pub fn release(self) -> I2C {
+            self.i2c
         }
-    }
 
-    /// reset S17021 device, called by init_device()
-    pub async fn reset_device(&mut self) -> Result<(), Error<E>> {
-        debug!("in reset_device()");
-        self.write_command([Si7021_RESET]).await?;
-        debug!("wrote reset command");
-        self.delayer.delay_ms(50).await;
-        Ok(())   
     }
 
-    /// initial S17021 device
-    pub async fn init_device(&mut self) -> Result<bool, Error<E>> {
-        debug!("in init_device()");
-        self.reset_device().await?;
-        #[allow(unused_parens)]
-        if (!self.is_connected().await?) {
-            return Ok(false);
-        } else {
-            debug!("device is connected");
-        }
-        #[allow(unused_parens)]
-        if (self.read_device_model().await? == DeviceModel::NotRead) {
-            debug!("device model is not read");
-            return Ok(false);
-        }
-        let firmware_result = self.read_firmware_version().await?;
-        debug!("fw version is {:#?}", firmware_result);
-        Ok(true)
-    }
+    impl<I2C, D, E> Si7021<I2C, D>
+    where  
+        I2C: AsyncI2c<Error = E>,
+        D: AsyncDelayNs,
+        E: I2cError,
+    {
+
+
+        // command_buf is an u8 array that starts with command byte followed by command data byte(s)
+        async fn write_command<const N: usize>(&mut self, command_buf: [u8; N] ) -> Result<(), Error<E>> {
+            // debug!("write_command : {:?}", command_buf);
+            self.end_pending_measurement();
+            self.i2c
+                .write(self.address, &command_buf).await
+                .map_err(|e| self.bus_error(command_buf[0], e))?;
+            Ok(())
+        }
 
-    /// read firmware version number of Si7021
-    pub async fn read_firmware_version(&mut self) -> Result<u8, Error<E>> {
-        let mut result_buf: [u8; 1] = [0; 1];
-        let command_buffer : [u8; 2] = [Si7021_READ_FW_VERSION[0], Si7021_READ_FW_VERSION[1]];
-        self.i2c
-            .write_read(self.address, &command_buffer, &mut result_buf).await
-            .map_err(Error::I2c)?;
-        #[allow(unused_parens)]
-        if (result_buf[0] == 0xff) {
-            self.device_data.firmware_version = 1;
-        } else if (result_buf[0] == 0x20) {
-            self.device_data.firmware_version = 2;
-        } else {
-            self.device_data.firmware_version = 0;  // is this an invalid value?
-        }
-        Ok(self.device_data.firmware_version.clone())
-    }
+        // any command ends a conversion started by start_*_measurement() or replaces its result, a later
+        // try_fetch_*() gives Error::MeasurementNotStarted instead of polling for a result that never comes
+        fn end_pending_measurement(&mut self) {
+            self.pending_command = None;
+        }
 
-    /// read device model 
-    pub async fn read_device_model(&mut self) -> Result<DeviceModel, Error<E>> {
-        let mut result_buf: [u8; 8] = [0; 8];
-        let mut command_buffer: [u8; 2] = [Si7021_READ_ID_BYTE_1[0], Si7021_READ_ID_BYTE_1[1]];
-        self.i2c
-            .write_read(self.address, &command_buffer, &mut result_buf).await
-            .map_err(Error::I2c)?;
-        let serial_id_a: u32 = u32::from_be_bytes([result_buf[0], result_buf[2], result_buf[4], result_buf[6] ]);
-        self.device_data.serial_a = serial_id_a;
-        debug!("serial_id_a = {}", serial_id_a);
-
-        command_buffer = [Si7021_READ_ID_BYTE_2[0], Si7021_READ_ID_BYTE_2[1]];
-        self.i2c
-            .write_read(self.address, &command_buffer, &mut result_buf).await
-            .map_err(Error::I2c)?;
-
-        let serial_id_b: u32 = u32::from_be_bytes( [result_buf[0], result_buf[1], result_buf[4], result_buf[5] ]);
-        self.device_data.serial_b = serial_id_b;
-        debug!("serial_id_b = {}", serial_id_b);
-        info!("device model byte is {:#04x} = {:#?}", result_buf[0], DeviceModel::from(result_buf[0]));
-        
-        self.device_data.id = DeviceModel::from(result_buf[0]);  // second user register byte 0 
-        Ok(DeviceModel::from(result_buf[0]))
-    }
+        // a NACK of the device address, HALs that cannot tell the NACK source apart report Unknown and
+        // are treated the same during detection and commands
+        fn is_address_nack(e: &E) -> bool {
+            matches!(e.kind(), ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address | NoAcknowledgeSource::Unknown))
+        }
 
-    /// read relative humidity in percent
-    pub async fn read_relative_humidity(&mut self) -> Result<f32, Error<E>> {
-        debug!("in read_relative_humidity()");
-        self.delayer.delay_ms(20).await;  // in case user called this again too quickly
-        let command_buffer: [u8; 1] = [Si7021_READ_RH_NO_HOLD]; 
-        self.i2c.write(self.address, &command_buffer).await
-            .map_err(Error::I2c)?;
-        self.delayer.delay_ms(25).await;
-        debug!("did i2c write, next is read");
-        let mut result_buf: [u8; 3] = [0; 3];
-        self.i2c.read(self.address, &mut result_buf).await
-            .map_err(Error::I2c)?;
-        
-        let humidity_u16 : u16 = u16::from_be_bytes( [result_buf[0], result_buf[1] ]);
-        // scale it
-        let mut humidity: f32 = ( humidity_u16 as f32 * 125.0 / 65536.0 ) - 6.0;
-        
-        // clamp value to => 0 and <= 100
-        #[allow(unused_parens)]
-        if (humidity.is_sign_negative() ) {
-            humidity = 0.0;
-        }
-        #[allow(unused_parens)]
-        if (humidity > 100.0) {
-            humidity = 100.0
-        }
-
-        Ok(humidity)
-        
+        // command rejected by the device during detection: a data NACK, or an address NACK since the device
+        // just ACKed its address in is_connected() (a HAL reporting NoAcknowledge(Unknown) for the command)
+        fn is_command_nack(error: &Error<E>, command: u8) -> bool {
+            match error {
+                Error::I2c(e) => matches!(e.kind(), ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data)),
+                Error::AddressNack { command: nacked, .. } => *nacked == command,
+                _ => false,
+            }
+        }
 
-    }
+        // I2C error of command, a NACK of the device address gets its own error with the address and command
+        fn bus_error(&self, command: u8, e: E) -> Error<E> {
+            if Self::is_address_nack(&e) {
+                debug!("address {:#x} NACK for command {:#x}", self.address, command);
+                return Error::AddressNack { address: self.address, command };
+            }
+            Error::I2c(e)
+        }
 
+        // compare the checksum byte sent by the device for command with the CRC-8 computed over data
+        fn check_crc(&self, command: u8, data: &[u8], crc: u8) -> Result<(), Error<E>> {
+            self.check_crc_value(command, crc8(data), crc)
+        }
 
-    /// read temperatue in degrees C
-    pub async fn read_temperature(&mut self) -> Result<f32, Error<E>> {
-        debug!("in read_temperature()");
-        let command_buffer: [u8; 1] = [Si7021_READ_TEMP_NO_HOLD]; 
-        self.i2c.write(self.address, &command_buffer).await
-                .map_err(Error::I2c)?;
-        self.delayer.delay_ms(20).await;
-        debug!("did i2c write, next is read");
-        let mut result_buf: [u8; 3] = [0; 3];
-        self.i2c.read(self.address, &mut result_buf).await
-                .map_err(Error::I2c)?;
-        
-        let temperature_u16 : u16 = u16::from_be_bytes( [result_buf[0], result_buf[1] ]);
-        // scale it
-        let temperature: f32 = ( temperature_u16 as f32 * 175.72 / 65536.0 ) - 46.85;
-
-        Ok(temperature)
-    }
+        // Error::Unsupported for commands an HTU21D/SHT21 compatible sensor does not have
+        fn require_si70xx(&self, command: u8) -> Result<(), Error<E>> {
+            if self.variant != SensorVariant::Si70xx {
+                debug!("command {:#x} not supported by {:?}", command, self.variant);
+                return Err(Error::Unsupported(command));
+            }
+            Ok(())
+        }
+
+        fn check_crc_value(&self, command: u8, expected: u8, actual: u8) -> Result<(), Error<E>> {
+            if self.crc_check && (expected != actual) {
+                debug!("CRC mismatch for command {:#x}, expected {:#x} got {:#x}", command, expected, actual);
+                return Err(Error::CrcMismatch { command, expected, actual });
+            }
+            Ok(())
+        }
+
+        // Error::NotInitialized before init_device()/detect() read the device model, Error::Unsupported
+        // if the device is not a Si7013
+        #[cfg(feature = "si7013")]
+        fn require_si7013(&self, command: u8) -> Result<(), Error<E>> {
+            self.require_si70xx(command)?;
+            match self.device_data.id {
+                DeviceModel::Si7013 | DeviceModel::SiEngineeringSample => Ok(()),
+                DeviceModel::NotRead => Err(Error::NotInitialized),
+                _ => Err(Error::Unsupported(command)),
+            }
+        }
+
+        async fn read_register( &mut self, register_address: u8, buffer: &mut [u8] ) -> Result<(), Error<E>> {
+            let mut command_buffer = [0u8; 1];
+            command_buffer[0] = register_address;
+            self.end_pending_measurement();
+            // let mut result_buffer = [0u8; N];
+            self.i2c
+                .write_read(self.address, &command_buffer, buffer).await
+                .map_err(|e| self.bus_error(register_address, e))?;
+            Ok(())
+        }
+
+
+        // run a humidity or temperature measurement in the selected measurement mode, returns the checked 16-bit code
+        async fn measure(&mut self, hold_command: u8, no_hold_command: u8, conversion_time_us: u32) -> Result<u16, Error<E>> {
+            let mut result_buf: [u8; 3] = [0; 3];
+            self.end_pending_measurement();
+            let command = match self.measurement_mode {
+                MeasurementMode::Hold => {
+                    self.i2c.write_read(self.address, &[hold_command], &mut result_buf).await
+                        .map_err(|e| self.bus_error(hold_command, e))?;
+                    hold_command
+                }
+                MeasurementMode::NoHold => {
+                    self.i2c.write(self.address, &[no_hold_command]).await
+                        .map_err(|e| self.bus_error(no_hold_command, e))?;
+                    debug!("did i2c write, next is read");
+                    if self.poll_interval_us == 0 {
+                        self.delayer.delay_us(conversion_time_us).await;
+                        self.i2c.read(self.address, &mut result_buf).await
+                            .map_err(|e| self.bus_error(no_hold_command, e))?;
+                    } else {
+                        self.poll_measurement(no_hold_command, &mut result_buf).await?;
+                    }
+                    no_hold_command
+                }
+            };
+            self.check_crc(command, &result_buf[0..2], result_buf[2])?;
+            Ok(u16::from_be_bytes([result_buf[0], result_buf[1]]))
+        }
+
+        // the Si70xx NACKs its read address until a No Hold Master Mode conversion is done, retry the read until
+        // it is ACKed or the measurement deadline passes
+        async fn poll_measurement(&mut self, command: u8, result_buf: &mut [u8]) -> Result<(), Error<E>> {
+            let mut waited_us: u32 = 0;
+            loop {
+                self.delayer.delay_us(self.poll_interval_us).await;
+                waited_us = waited_us.saturating_add(self.poll_interval_us);
+                match self.i2c.read(self.address, result_buf).await {
+                    Ok(()) => {
+                        debug!("conversion done after {} usec", waited_us);
+                        return Ok(());
+                    }
+                    Err(e) if matches!(e.kind(), ErrorKind::NoAcknowledge(_)) => {
+                        if waited_us >= self.measurement_timeout_us() {
+                            debug!("no measurement data after {} usec", waited_us);
+                            return Err(Error::MeasurementTimeout { command, waited_us });
+                        }
+                    }
+                    Err(e) => return Err(self.bus_error(command, e)),
+                }
+            }
+        }
+
+        // a NACK of the device address means nothing answers at this address
+        fn not_connected_on_nack(e: E) -> Error<E> {
+            if Self::is_address_nack(&e) {
+                return Error::NotConnected;
+            }
+            Error::I2c(e)
+        }
+
+        /// check if Si7021 is connected: Ok if the device ACKs a read of RH/T User Register 1 (any value),
+        /// Error::NotConnected if the bus NACKs
+        pub async fn is_connected(&mut self) -> Result<(), Error<E>> {
+            debug!("in is_connected()");
+            let mut result_buf: [u8; 1] = [0; 1];
+            self.end_pending_measurement();
+            self.i2c
+                .write_read(self.address, &[Si7021_READ_RH_T_USER_REG_1], &mut result_buf).await
+                .map_err(Self::not_connected_on_nack)?;
+            debug!(" is_connected() user register 1 = {:#x}", result_buf[0]);
+            Ok(())
+        }
+
+        /// detect the connected device by its electronic ID, returns model, firmware version and serial numbers
+        /// (also stored in device_data), Error::NotConnected if the bus NACKs. A device that NACKs the
+        /// firmware revision command is an HTU21D/SHT21 compatible sensor (device_data.id is then
+        /// DeviceModel::NotRead), otherwise an unknown ID byte gives Error::UnexpectedDeviceId
+        /// (device_data.id is then DeviceModel::Unknown)
+        pub async fn detect(&mut self) -> Result<DetectedDevice, Error<E>> {
+            debug!("in detect()");
+            self.is_connected().await?;
+            self.variant = SensorVariant::Si70xx;
+            self.device_data = DeviceData::default();
+            // HTU21D/SHT21 parts answer the electronic ID commands with SNC_1 in the Si70xx device ID position
+            // (or NACK them), so the ID alone does not tell them apart. They lack the firmware revision command.
+            // ID errors (unknown ID byte, checksum) are returned only if the device turns out to be a Si70xx
+            let model = self.read_device_model().await;
+            match model {
+                Ok(_) | Err(Error::UnexpectedDeviceId(_)) | Err(Error::CrcMismatch { .. }) => {}
+                Err(ref e) if Self::is_command_nack(e, Si7021_READ_ID_BYTE_1[0]) => {}
+                Err(e) => return Err(e),
+            }
+            match self.read_firmware_version().await {
+                Ok(_) => {
+                    model?;
+                }
+                Err(ref e) if Self::is_command_nack(e, Si7021_READ_FW_VERSION[0]) => {
+                    self.variant = SensorVariant::Sht21Compatible;
+                    self.device_data.id = DeviceModel::NotRead;
+                }
+                Err(e) => return Err(e),
+            }
+            debug!("sensor variant is {:?}", self.variant);
+            Ok(DetectedDevice {
+                id: self.device_data.id,
+                firmware_version: self.device_data.firmware_version,
+                serial: self.device_data.serial,
+                variant: self.variant,
+            })
+        }
+
+        /// reset S17021 device, called by init_device()
+        pub async fn reset_device(&mut self) -> Result<(), Error<E>> {
+            debug!("in reset_device()");
+            self.end_pending_measurement();
+            self.i2c
+                .write(self.address, &[Si7021_RESET]).await
+                .map_err(Self::not_connected_on_nack)?;
+            debug!("wrote reset command");
+            self.delayer.delay_ms(50).await;
+            self.resolution = Resolution::default();  // reset restores the power-on resolution
+            Ok(())   
+        }
+
+        /// initial S17021 device: reset and detect, Error::NotConnected if the bus NACKs
+        pub async fn init_device(&mut self) -> Result<(), Error<E>> {
+            debug!("in init_device()");
+            self.reset_device().await?;
+            let detected = self.detect().await?;
+            let secondary_supported = (detected.variant == SensorVariant::Si70xx) && detected.id.supports_address(DeviceAddress::Secondary);
+            if (self.address == u8::from(DeviceAddress::Secondary)) && !secondary_supported {
+                debug!("{:?} does not support address {:#x}", detected.id, self.address);
+                return Err(Error::AddressNotSupported(self.address));
+            }
+            debug!("fw version is {:?}", detected.firmware_version);
+            Ok(())
+        }
 
-    /// read measurements (temperature and humidity as a struct)
-    pub async fn read_measurements(&mut self) -> Result<Measurements, Error<E>> {
-        debug!("in read_measurements()");
-        let humidity = self.read_relative_humidity().await?;
-        //self.delayer.delay_ms(5);
-        let mut result_buf: [u8; 2] = [0; 2];
-        self.read_register(Si7021_READ_TEMP_AFTER_PREVIOUS_RH, &mut result_buf).await?;
-        let temperature_u16 : u16 = u16::from_be_bytes( [result_buf[0], result_buf[1] ]);
-        // scale it
-        let temperature: f32 = ( temperature_u16 as f32 * 175.72 / 65536.0 ) - 46.85;
-
-        let measurements: Measurements = Measurements {
-            relative_humidity_percent: humidity,
-            temperature_c: temperature,
-        };
-        Ok(measurements)
-    }  
+        /// read firmware version number of Si7021
+        pub async fn read_firmware_version(&mut self) -> Result<FirmwareRevision, Error<E>> {
+            self.require_si70xx(Si7021_READ_FW_VERSION[0])?;
+            let mut result_buf: [u8; 1] = [0; 1];
+            let command_buffer : [u8; 2] = [Si7021_READ_FW_VERSION[0], Si7021_READ_FW_VERSION[1]];
+            self.end_pending_measurement();
+            self.i2c
+                .write_read(self.address, &command_buffer, &mut result_buf).await
+                .map_err(|e| self.bus_error(command_buffer[0], e))?;
+            self.device_data.firmware_version = FirmwareRevision::from(result_buf[0]);
+            Ok(self.device_data.firmware_version)
+        }
+
+        /// read device model 
+        pub async fn read_device_model(&mut self) -> Result<DeviceModel, Error<E>> {
+            self.require_si70xx(Si7021_READ_ID_BYTE_1[0])?;
+            let mut result_buf: [u8; 8] = [0; 8];
+            let command_buffer: [u8; 2] = [Si7021_READ_ID_BYTE_1[0], Si7021_READ_ID_BYTE_1[1]];
+            self.end_pending_measurement();
+            self.i2c
+                .write_read(self.address, &command_buffer, &mut result_buf).await
+                .map_err(|e| self.bus_error(command_buffer[0], e))?;
+            // SNA_3, CRC, SNA_2, CRC, SNA_1, CRC, SNA_0, CRC : each CRC covers all SNA bytes sent so far
+            let mut crc: u8 = 0x00;
+            for pair in result_buf.chunks_exact(2) {
+                crc = crc8_update(crc, pair[0]);
+                self.check_crc_value(Si7021_READ_ID_BYTE_1[0], crc, pair[1])?;
+            }
+            let serial_id_a: u32 = u32::from_be_bytes([result_buf[0], result_buf[2], result_buf[4], result_buf[6] ]);
+            debug!("serial_id_a = {}", serial_id_a);
+
+            let mut result_buf: [u8; 6] = [0; 6];
+            let command_buffer: [u8; 2] = [Si7021_READ_ID_BYTE_2[0], Si7021_READ_ID_BYTE_2[1]];
+            self.i2c
+                .write_read(self.address, &command_buffer, &mut result_buf).await
+                .map_err(|e| self.bus_error(command_buffer[0], e))?;
+            // SNB_3, SNB_2, CRC, SNB_1, SNB_0, CRC : each CRC covers all SNB bytes sent so far
+            self.check_crc(Si7021_READ_ID_BYTE_2[0], &result_buf[0..2], result_buf[2])?;
+            self.check_crc(Si7021_READ_ID_BYTE_2[0], &[result_buf[0], result_buf[1], result_buf[3], result_buf[4]], result_buf[5])?;
+
+            let serial_id_b: u32 = u32::from_be_bytes( [result_buf[0], result_buf[1], result_buf[3], result_buf[4] ]);
+            debug!("serial_id_b = {}", serial_id_b);
+            self.device_data.serial = SerialNumber::new(serial_id_a, serial_id_b);
+            // SNB_3 is the device ID byte
+            match DeviceModel::try_from(result_buf[0]) {
+                Ok(model) => {
+                    info!("device model byte is {:#x} = {:?}", result_buf[0], model);
+                    self.device_data.id = model;
+                    Ok(model)
+                }
+                Err(id) => {
+                    info!("unknown device model byte {:#x}", id);
+                    self.device_data.id = DeviceModel::Unknown(id);
+                    Err(Error::UnexpectedDeviceId(id))
+                }
+            }
+        }
+
+        /// read the 16-bit relative humidity code (including the status bits), see conversion::rh_from_code()
+        pub async fn read_raw_humidity(&mut self) -> Result<u16, Error<E>> {
+            debug!("in read_raw_humidity()");
+            let conversion_time_us = self.variant.humidity_conversion_time_us(self.resolution);
+            self.measure(Si7021_READ_RH_HOLD, Si7021_READ_RH_NO_HOLD, conversion_time_us).await
+        }
+
+        /// read the 16-bit temperature code (including the status bits), see conversion::temperature_from_code()
+        pub async fn read_raw_temperature(&mut self) -> Result<u16, Error<E>> {
+            debug!("in read_raw_temperature()");
+            let conversion_time_us = self.variant.temperature_conversion_time_us(self.resolution);
+            self.measure(Si7021_READ_TEMP_HOLD, Si7021_READ_TEMP_NO_HOLD, conversion_time_us).await
+        }
+
+        /// read relative humidity in percent
+        #[cfg(feature = "float")]
+        pub async fn read_relative_humidity(&mut self) -> Result<f32, Error<E>> {
+            debug!("in read_relative_humidity()");
+            let humidity_u16 : u16 = self.read_raw_humidity().await?;
+            Ok(clamped_rh_from_code(humidity_u16))
+        }
+
+
+        /// read relative humidity without clamping, with the raw code and saturation flags
+        #[cfg(feature = "float")]
+        pub async fn read_humidity_reading(&mut self) -> Result<HumidityReading, Error<E>> {
+            debug!("in read_humidity_reading()");
+            let humidity_u16 : u16 = self.read_raw_humidity().await?;
+            Ok(HumidityReading::from_code(humidity_u16))
+        }
+
+        /// read temperatue in degrees C
+        #[cfg(feature = "float")]
+        pub async fn read_temperature(&mut self) -> Result<f32, Error<E>> {
+            debug!("in read_temperature()");
+            let temperature_u16 : u16 = self.read_raw_temperature().await?;
+            Ok(temperature_from_code(temperature_u16))
+        }
+
+        /// read measurements (temperature and humidity as a struct)
+        #[cfg(feature = "float")]
+        pub async fn read_measurements(&mut self) -> Result<Measurements, Error<E>> {
+            debug!("in read_measurements()");
+            let (humidity_u16, temperature_u16, vdd_low) = self.read_measurement_codes().await?;
+
+            let measurements: Measurements = Measurements {
+                relative_humidity_percent: clamped_rh_from_code(humidity_u16),
+                temperature_c: temperature_from_code(temperature_u16),
+                vdd_low,
+            };
+            Ok(measurements)
+        }  
+
+        /// read measurements with integer arithmetic only (centi-degrees C and milli-percent RH)
+        pub async fn read_measurements_int(&mut self) -> Result<IntegerMeasurements, Error<E>> {
+            debug!("in read_measurements_int()");
+            let (humidity_u16, temperature_u16, vdd_low) = self.read_measurement_codes().await?;
+
+            let measurements: IntegerMeasurements = IntegerMeasurements {
+                relative_humidity_milli_percent: milli_percent_rh_from_code(humidity_u16),
+                temperature_centi_c: centi_celsius_from_code(temperature_u16),
+                vdd_low,
+            };
+            Ok(measurements)
+        }
+
+        /// read relative humidity in milli-percent (integer arithmetic only)
+        pub async fn read_relative_humidity_milli_percent(&mut self) -> Result<u32, Error<E>> {
+            let humidity_u16 : u16 = self.read_raw_humidity().await?;
+            Ok(milli_percent_rh_from_code(humidity_u16))
+        }
+
+        /// read temperature in centi-degrees C (integer arithmetic only)
+        pub async fn read_temperature_centi_c(&mut self) -> Result<i32, Error<E>> {
+            let temperature_u16 : u16 = self.read_raw_temperature().await?;
+            Ok(centi_celsius_from_code(temperature_u16))
+        }
+
+        // RH code, temperature code (from the RH measurement when supported) and VDD low status
+        async fn read_measurement_codes(&mut self) -> Result<(u16, u16, bool), Error<E>> {
+            let humidity_u16 = self.read_raw_humidity().await?;
+            let temperature_u16 = if self.variant == SensorVariant::Si70xx {
+                let mut result_buf: [u8; 2] = [0; 2];
+                self.read_register(Si7021_READ_TEMP_AFTER_PREVIOUS_RH, &mut result_buf).await?;
+                u16::from_be_bytes( [result_buf[0], result_buf[1] ])
+            } else {
+                // no temperature from previous RH measurement, measure it
+                self.read_raw_temperature().await?
+            };
+            let vdd_low = self.is_vdd_low().await?;
+            Ok((humidity_u16, temperature_u16, vdd_low))
+        }
   
-    /// heater control enable/disable
-    pub async fn heater_control(&mut self, enable: bool) -> Result<(), Error<E>> {
-        debug!("in heater_control({})", enable);
-        let mut result_buf: [u8; 1] = [0; 1];
-        self.read_register(Si7021_READ_RH_T_USER_REG_1, &mut result_buf).await?;
-        let mut write_value = result_buf[0];
-        if enable {
-            write_value = write_value | (1 << 2);
-        } else {
-            write_value = write_value & !(1 << 2);
-        }
-        self.write_command([Si7021_WRITE_RH_T_USER_REG_1, write_value]).await?;
-
-        Ok(())
-    }
+        /// read RH/T User Register 1
+        pub async fn read_user_register(&mut self) -> Result<UserRegister1, Error<E>> {
+            let mut result_buf: [u8; 1] = [0; 1];
+            self.read_register(Si7021_READ_RH_T_USER_REG_1, &mut result_buf).await?;
+            let user_register = UserRegister1::from(result_buf[0]);
+            self.resolution = user_register.resolution;
+            Ok(user_register)
+        }
 
-    /// is heater enabled
-    pub async fn is_heater_enabled(&mut self) -> Result<bool, Error<E>> {
-        debug!("in is_heater_enabled()");
-        let mut result_buf: [u8; 1] = [0; 1];
-        self.read_register(Si7021_READ_RH_T_USER_REG_1, &mut result_buf).await?;
-        let read_value = result_buf[0];
-        #[allow(unused_parens)]
-        if ((read_value & 0x04) != 0x00)  {
-            Ok(true)
-        } else {
-            Ok(false)
+        /// write RH/T User Register 1, reserved bits are written back as read and VDDS is read only
+        pub async fn write_user_register(&mut self, user_register: UserRegister1) -> Result<(), Error<E>> {
+            debug!("in write_user_register({:?})", user_register);
+            let mut write_register = user_register;
+            write_register.vdd_low = false;
+            let write_value = u8::from(write_register);
+            self.write_command([Si7021_WRITE_RH_T_USER_REG_1, write_value]).await?;
+            self.resolution = user_register.resolution;
+            Ok(())
+        }
+
+        /// read-modify-write RH/T User Register 1, returns the value written
+        pub async fn modify_user_register<F>(&mut self, modify: F) -> Result<UserRegister1, Error<E>>
+        where
+            F: FnOnce(&mut UserRegister1),
+        {
+            let mut user_register = self.read_user_register().await?;
+            modify(&mut user_register);
+            self.write_user_register(user_register).await?;
+            Ok(user_register)
         }
-    }
 
-    /// set heater power level
-    pub async fn set_heater_level(&mut self, level: u8) -> Result<(), Error<E>> {
-        debug!("in set_heater_level({})", level);
-        #[allow(unused_parens)]
-        if (level > 0x0f) {
-            return Err(Error::OutOfRange(level));
+        /// heater control enable/disable
+        pub async fn heater_control(&mut self, enable: bool) -> Result<(), Error<E>> {
+            debug!("in heater_control({})", enable);
+            self.modify_user_register(|r| r.heater_enabled = enable).await?;
+            Ok(())
         }
-        self.write_command([Si7021_WRITE_HEATER_CONTROL, level]).await?;
-        Ok(())
+
+        /// is heater enabled
+        pub async fn is_heater_enabled(&mut self) -> Result<bool, Error<E>> {
+            debug!("in is_heater_enabled()");
+            Ok(self.read_user_register().await?.heater_enabled)
+        }
+
+        /// is VDD low, User Register 1 VDDS bit is set when VDD drops below the minimum
+        /// recommended operating voltage (about 1.9V) and measurements may not be reliable
+        pub async fn is_vdd_low(&mut self) -> Result<bool, Error<E>> {
+            debug!("in is_vdd_low()");
+            Ok(self.read_user_register().await?.vdd_low)
+        }
+
+        /// set heater power level
+        pub async fn set_heater_level(&mut self, level: u8) -> Result<(), Error<E>> {
+            debug!("in set_heater_level({})", level);
+            self.require_si70xx(Si7021_WRITE_HEATER_CONTROL)?;
+            #[allow(unused_parens)]
+            if (level > 0x0f) {
+                return Err(Error::OutOfRange(level));
+            }
+            self.write_command([Si7021_WRITE_HEATER_CONTROL, level]).await?;
+            Ok(())
+        }
+
+        /// get heater power level from the Heater Control Register
+        pub async fn get_heater_level(&mut self) -> Result<HeaterLevel, Error<E>> {
+            debug!("in get_heater_level()");
+            self.require_si70xx(Si7021_READ_HEATER_CONTROL)?;
+            let mut result_buf: [u8; 1] = [0; 1];
+            self.read_register(Si7021_READ_HEATER_CONTROL, &mut result_buf).await?;
+            Ok(HeaterLevel::from_register(result_buf[0]))
+        }
+
+        /// set RH and temperature measurement resolution, lower resolution gives much shorter conversion times
+        pub async fn set_resolution(&mut self, resolution: Resolution) -> Result<(), Error<E>> {
+            debug!("in set_resolution({:?})", resolution);
+            self.modify_user_register(|r| r.resolution = resolution).await?;
+            Ok(())
+        }
+
+        /// get RH and temperature measurement resolution
+        pub async fn get_resolution(&mut self) -> Result<Resolution, Error<E>> {
+            debug!("in get_resolution()");
+            Ok(self.read_user_register().await?.resolution)
+        }
+
+        /// read Si7013 Voltage Measurement Setup (User Register 2)
+        #[cfg(feature = "si7013")]
+        pub async fn read_user_register_2(&mut self) -> Result<UserRegister2, Error<E>> {
+            self.require_si7013(Si7013_READ_USER_REG_2)?;
+            let mut result_buf: [u8; 1] = [0; 1];
+            self.read_register(Si7013_READ_USER_REG_2, &mut result_buf).await?;
+            Ok(UserRegister2::from(result_buf[0]))
+        }
+
+        /// write Si7013 Voltage Measurement Setup (User Register 2), reserved bits are written back as read
+        #[cfg(feature = "si7013")]
+        pub async fn write_user_register_2(&mut self, user_register: UserRegister2) -> Result<(), Error<E>> {
+            debug!("in write_user_register_2({:?})", user_register);
+            self.require_si7013(Si7013_WRITE_USER_REG_2)?;
+            self.write_command([Si7013_WRITE_USER_REG_2, u8::from(user_register)]).await
+        }
+
+        /// read Si7013 analog input (VIN) as the raw 16-bit ADC code, in the selected measurement mode
+        #[cfg(feature = "si7013")]
+        pub async fn read_analog_raw(&mut self) -> Result<u16, Error<E>> {
+            debug!("in read_analog_raw()");
+            Ok(self.measure_analog().await?.0)
+        }
+
+        // Measure Analog Voltage in the selected measurement mode: the NO_HOLD bit of User Register 2 selects
+        // the mode of command 0xEE and is updated first if needed. Returns the code and User Register 2
+        #[cfg(feature = "si7013")]
+        async fn measure_analog(&mut self) -> Result<(u16, UserRegister2), Error<E>> {
+            self.require_si7013(Si7013_MEASURE_ANALOG)?;
+            let mut user_register = self.read_user_register_2().await?;
+            let no_hold = self.measurement_mode == MeasurementMode::NoHold;
+            if user_register.no_hold != no_hold {
+                user_register.no_hold = no_hold;
+                self.write_user_register_2(user_register).await?;
+            }
+            let analog_u16 = self.measure(Si7013_MEASURE_ANALOG, Si7013_MEASURE_ANALOG, SI7013_ANALOG_CONVERSION_TIME_US).await?;
+            Ok((analog_u16, user_register))
+        }
+
+        /// read Si7013 analog input voltage in volts, reference_volts is 1.25 for the internal
+        /// reference or the VDDA supply voltage when VDDA is selected in User Register 2
+        #[cfg(all(feature = "si7013", feature = "float"))]
+        pub async fn read_analog_voltage(&mut self, reference_volts: f32) -> Result<f32, Error<E>> {
+            let analog_u16 = self.read_analog_raw().await?;
+            Ok(analog_u16 as f32 * reference_volts / 32768.0)
+        }
+
+        /// write the Si7013 thermistor correction coefficient table and enable thermistor correction,
+        /// VOUT is driven high to bias the thermistor divider
+        #[cfg(feature = "si7013")]
+        pub async fn configure_thermistor(&mut self, config: &ThermistorConfig<'_>) -> Result<(), Error<E>> {
+            debug!("in configure_thermistor()");
+            self.require_si7013(Si7013_WRITE_THERMISTOR_COEFF)?;
+            for (address, value) in config.coefficients {
+                self.write_command([Si7013_WRITE_THERMISTOR_COEFF, *address, *value]).await?;
+            }
+            let mut user_register = self.read_user_register_2().await?;
+            user_register.reference = config.reference;
+            user_register.vin_buffered = config.vin_buffered;
+            user_register.vout_high = true;
+            user_register.thermistor_correction = true;
+            self.write_user_register_2(user_register).await
+        }
+
+        /// read a Si7013 thermistor correction coefficient
+        #[cfg(feature = "si7013")]
+        pub async fn read_thermistor_coefficient(&mut self, address: u8) -> Result<u8, Error<E>> {
+            self.require_si7013(Si7013_READ_THERMISTOR_COEFF)?;
+            let mut result_buf: [u8; 1] = [0; 1];
+            self.end_pending_measurement();
+            self.i2c.write_read(self.address, &[Si7013_READ_THERMISTOR_COEFF, address], &mut result_buf).await
+                .map_err(|e| self.bus_error(Si7013_READ_THERMISTOR_COEFF, e))?;
+            Ok(result_buf[0])
+        }
+
+        /// read Si7013 external thermistor temperature in degrees C. With THERM_CORR set the Si7013 returns the
+        /// analog input code mapped by the coefficient table written with configure_thermistor(), which has
+        /// to produce temperature codes (see ThermistorConfig). Error::ThermistorNotConfigured if thermistor
+        /// correction is off, the result would be an uncorrected ADC code
+        #[cfg(all(feature = "si7013", feature = "float"))]
+        pub async fn read_thermistor_temperature(&mut self) -> Result<f32, Error<E>> {
+            debug!("in read_thermistor_temperature()");
+            let (temperature_u16, user_register) = self.measure_analog().await?;
+            if !user_register.thermistor_correction {
+                return Err(Error::ThermistorNotConfigured);
+            }
+            Ok(temperature_from_code(temperature_u16))
+        }
+
     }
 
-}
\ No newline at end of file
+    #[maybe_async_cfg::only_if(sync)]
+    impl<I2C, D, E> Si7021<I2C, D>
+    where  
+        I2C: I2c<Error = E>,
+        D: DelayNs,
+        E: I2cError,
+    {
+    
+        /// start a No Hold Master Mode relative humidity measurement, get the result with try_fetch_humidity()
+        pub fn start_humidity_measurement(&mut self) -> Result<(), Error<E>> {
+            debug!("in start_humidity_measurement()");
+            self.write_command([Si7021_READ_RH_NO_HOLD])?;
+            self.pending_command = Some(Si7021_READ_RH_NO_HOLD);
+            Ok(())
+        }
+
+        /// start a No Hold Master Mode temperature measurement, get the result with try_fetch_temperature()
+        pub fn start_temperature_measurement(&mut self) -> Result<(), Error<E>> {
+            debug!("in start_temperature_measurement()");
+            self.write_command([Si7021_READ_TEMP_NO_HOLD])?;
+            self.pending_command = Some(Si7021_READ_TEMP_NO_HOLD);
+            Ok(())
+        }
+
+        /// fetch relative humidity in percent started by start_humidity_measurement(),
+        /// nb::Error::WouldBlock while the Si70xx is still converting
+        #[cfg(feature = "float")]
+        pub fn try_fetch_humidity(&mut self) -> nb::Result<f32, Error<E>> {
+            let humidity_u16 = self.try_fetch_code(Some(Si7021_READ_RH_NO_HOLD))?;
+            Ok(clamped_rh_from_code(humidity_u16))
+        }
+
+        /// fetch temperature in degrees C started by start_temperature_measurement(),
+        /// nb::Error::WouldBlock while the Si70xx is still converting
+        #[cfg(feature = "float")]
+        pub fn try_fetch_temperature(&mut self) -> nb::Result<f32, Error<E>> {
+            let temperature_u16 = self.try_fetch_code(Some(Si7021_READ_TEMP_NO_HOLD))?;
+            Ok(temperature_from_code(temperature_u16))
+        }
+
+        /// fetch the 16-bit measurement code of a started measurement (see conversion module),
+        /// nb::Error::WouldBlock while the Si70xx is still converting (it NACKs its read address),
+        /// Error::MeasurementNotStarted if no measurement was started since the last fetch or another command ended it
+        pub fn try_fetch_raw(&mut self) -> nb::Result<u16, Error<E>> {
+            self.try_fetch_code(None)
+        }
+
+        // fetch the code of the pending measurement, requested is the No Hold command the caller expects
+        // (None for any). The measurement stays pending on WouldBlock and is done after any other result
+        fn try_fetch_code(&mut self, requested: Option<u8>) -> nb::Result<u16, Error<E>> {
+            let started = self.pending_command.ok_or(Error::MeasurementNotStarted)?;
+            if let Some(requested) = requested.filter(|requested| *requested != started) {
+                return Err(nb::Error::Other(Error::MeasurementMismatch { started, requested }));
+            }
+            let mut result_buf: [u8; 3] = [0; 3];
+            match self.i2c.read(self.address, &mut result_buf) {
+                Ok(()) => {}
+                Err(e) if matches!(e.kind(), ErrorKind::NoAcknowledge(_)) => return Err(nb::Error::WouldBlock),
+                Err(e) => {
+                    self.pending_command = None;
+                    return Err(nb::Error::Other(self.bus_error(started, e)));
+                }
+            }
+            self.pending_command = None;
+            self.check_crc(started, &result_buf[0..2], result_buf[2])?;
+            Ok(u16::from_be_bytes([result_buf[0], result_buf[1]]))
+        }
+
+    }
+}
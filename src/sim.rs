@@ -0,0 +1,395 @@
+// Si7021 model on an emulated I2C bus for host side testing without hardware (feature "sim")
+
+//! Emulated Si70xx sensor implementing the embedded-hal (and with feature "async" the embedded-hal-async)
+//! I2C traits, so the full driver can run on a host.
+//!
+//! ```
+//! use si7021_t_rh::blocking::Si7021;
+//! use si7021_t_rh::data::DeviceModel;
+//! use si7021_t_rh::sim::{SimDelay, SimSi7021};
+//!
+//! let mut sensor = SimSi7021::new();
+//! sensor.set_temperature_centi_c(2150);
+//! sensor.set_relative_humidity_milli_percent(45_000);
+//! sensor.set_conversion_nacks(3);
+//!
+//! let mut si7021 = Si7021::new(&mut sensor, SimDelay::new());
+//! si7021.init_device().unwrap();
+//! assert_eq!(si7021.device_data.id, DeviceModel::Si7021);
+//! assert_eq!(si7021.read_temperature_centi_c().unwrap(), 2150);
+//! let measurements = si7021.read_measurements_int().unwrap();
+//! assert_eq!(measurements.relative_humidity_milli_percent, 45_000);
+//! assert_eq!(measurements.temperature_centi_c, 2150);
+//!
+//! si7021.heater_control(true).unwrap();
+//! si7021.set_heater_level(5).unwrap();
+//! drop(si7021);
+//! assert_eq!(sensor.user_register_1() & 0x04, 0x04);
+//! assert_eq!(sensor.heater_control(), 5);
+//! ```
+
+use embedded_hal::i2c::{ErrorKind, ErrorType, NoAcknowledgeSource, Operation};
+
+use crate::constants::{DeviceAddress, Si7013_MEASURE_ANALOG, Si7013_READ_THERMISTOR_COEFF, Si7013_READ_USER_REG_2, Si7013_WRITE_THERMISTOR_COEFF, Si7013_WRITE_USER_REG_2};
+use crate::constants::{Si7021_READ_FW_VERSION, Si7021_READ_HEATER_CONTROL, Si7021_READ_ID_BYTE_1, Si7021_READ_ID_BYTE_2, Si7021_READ_RH_HOLD, Si7021_READ_RH_NO_HOLD, Si7021_READ_RH_T_USER_REG_1, Si7021_READ_TEMP_AFTER_PREVIOUS_RH, Si7021_READ_TEMP_HOLD, Si7021_READ_TEMP_NO_HOLD, Si7021_RESET, Si7021_WRITE_HEATER_CONTROL, Si7021_WRITE_RH_T_USER_REG_1};
+use crate::crc::{crc8, crc8_update};
+use crate::data::{SensorVariant, SerialNumber, UserRegister1};
+
+/// emulated Si70xx (or HTU21D/SHT21 compatible) sensor, answers the I2C commands of the datasheet
+/// with its register contents and the configured measurement codes
+#[derive(Debug, Clone)]
+pub struct SimSi7021 {
+    address: u8,
+    connected: bool,
+    variant: SensorVariant,
+    serial: SerialNumber,
+    firmware: u8,
+    user_register_1: u8,
+    heater_control: u8,
+    user_register_2: u8,
+    thermistor_coefficients: [u8; 256],
+    humidity_code: u16,
+    temperature_code: u16,
+    analog_code: u16,
+    /// temperature code of the last RH measurement, read with Si7021_READ_TEMP_AFTER_PREVIOUS_RH
+    previous_rh_temperature_code: u16,
+    /// read NACKs of a No Hold Master Mode measurement before the result is ready
+    conversion_nacks: u32,
+    /// read NACKs left for the running No Hold Master Mode measurement
+    nacks_left: u32,
+    crc_error: bool,
+    /// answer for the next read
+    response: [u8; 8],
+    response_len: usize,
+}
+
+impl SimSi7021 {
+    /// Si7013 User Register 2 after reset: all options off (VOUT low, internal reference, VIN unbuffered,
+    /// no thermistor correction, Hold Master Mode for 0xEE), reserved bits read as 0 in this emulation
+    const USER_REGISTER_2_RESET: u8 = 0x00;
+    /// User Register 2 NO_HOLD bit (D6), Measure Analog Voltage in No Hold Master Mode
+    const NO_HOLD: u8 = 0x40;
+    /// Heater Control Register value after reset
+    const HEATER_CONTROL_RESET: u8 = 0x00;
+    const VDDS: u8 = 0x40;
+
+    /// Si7021 firmware 2.0 at the primary address, 25 C and 50 %RH, results ready without NACKs
+    pub fn new() -> Self {
+        SimSi7021 {
+            address: DeviceAddress::Primary.into(),
+            connected: true,
+            variant: SensorVariant::Si70xx,
+            serial: SerialNumber::new(0x1234_5678, 0x1500_9abc),
+            firmware: 0x20,
+            user_register_1: UserRegister1::RESET_VALUE,
+            heater_control: Self::HEATER_CONTROL_RESET,
+            user_register_2: Self::USER_REGISTER_2_RESET,
+            thermistor_coefficients: [0; 256],
+            humidity_code: 0x72b0,
+            temperature_code: 0x68ad,
+            analog_code: 0x0000,
+            previous_rh_temperature_code: 0x68ad,
+            conversion_nacks: 0,
+            nacks_left: 0,
+            crc_error: false,
+            response: [0; 8],
+            response_len: 0,
+        }
+    }
+
+    /// answer at this I2C address, Secondary (0x41) is the Si7013 with AD0 high
+    pub fn set_address(&mut self, address: DeviceAddress) {
+        self.address = address.into();
+    }
+
+    /// false: every transaction is NACKed on the address as if no sensor is on the bus
+    pub fn set_connected(&mut self, connected: bool) {
+        self.connected = connected;
+    }
+
+    /// Sht21Compatible NACKs the firmware revision, heater and previous RH temperature commands like an HTU21D.
+    /// It answers the electronic ID commands with the serial number, an HTU21D sends SNC_1 where the Si70xx
+    /// has its device ID byte (set_device_id())
+    pub fn set_sensor_variant(&mut self, variant: SensorVariant) {
+        self.variant = variant;
+    }
+
+    /// electronic ID serial number, SNB_3 (the most significant SNB byte) is the device ID byte
+    pub fn set_serial(&mut self, serial: SerialNumber) {
+        self.serial = serial;
+    }
+
+    /// device ID byte (SNB_3), for example 0x15 for the Si7021 or 0x0d for the Si7013
+    pub fn set_device_id(&mut self, id: u8) {
+        let snb = (self.serial.snb() & 0x00ff_ffff) | (u32::from(id) << 24);
+        self.serial = SerialNumber::new(self.serial.sna(), snb);
+    }
+
+    /// firmware revision byte, 0xff for firmware 1.0 and 0x20 for firmware 2.0
+    pub fn set_firmware_byte(&mut self, firmware: u8) {
+        self.firmware = firmware;
+    }
+
+    /// 16-bit code returned by RH measurements
+    pub fn set_humidity_code(&mut self, code: u16) {
+        self.humidity_code = code;
+    }
+
+    /// 16-bit code returned by temperature measurements
+    pub fn set_temperature_code(&mut self, code: u16) {
+        self.temperature_code = code;
+    }
+
+    /// relative humidity in percent returned by RH measurements
+    #[cfg(feature = "float")]
+    pub fn set_relative_humidity(&mut self, humidity: f32) {
+        self.humidity_code = crate::conversion::code_from_rh(humidity);
+    }
+
+    /// temperature in degrees C returned by temperature measurements
+    #[cfg(feature = "float")]
+    pub fn set_temperature(&mut self, temperature: f32) {
+        self.temperature_code = crate::conversion::code_from_temperature(temperature);
+    }
+
+    /// relative humidity in milli-percent returned by RH measurements, integer arithmetic only
+    pub fn set_relative_humidity_milli_percent(&mut self, humidity_milli_percent: u32) {
+        // inverse of conversion::milli_percent_rh_from_code() rounded up, one code is about 1.9 milli-percent so
+        // the driver reads back the value or up to 2 milli-percent more
+        let code = ((i64::from(humidity_milli_percent) + 6_000) * 65536 + 124_999) / 125_000;
+        self.humidity_code = code.clamp(0, 0xffff) as u16;
+    }
+
+    /// temperature in centi-degrees C returned by temperature measurements, integer arithmetic only
+    pub fn set_temperature_centi_c(&mut self, temperature_centi_c: i32) {
+        // inverse of conversion::centi_celsius_from_code() rounded up, so the driver reads the same value back
+        let code = ((i64::from(temperature_centi_c) + 4_685) * 65536 + 17_571) / 17_572;
+        self.temperature_code = code.clamp(0, 0xffff) as u16;
+    }
+
+    /// Si7013 analog input code returned by Measure Analog Voltage
+    pub fn set_analog_code(&mut self, code: u16) {
+        self.analog_code = code;
+    }
+
+    /// VDDS bit of User Register 1, supply voltage below the minimum operating voltage
+    pub fn set_vdd_low(&mut self, vdd_low: bool) {
+        if vdd_low {
+            self.user_register_1 |= Self::VDDS;
+        } else {
+            self.user_register_1 &= !Self::VDDS;
+        }
+    }
+
+    /// number of reads NACKed after a No Hold Master Mode measurement command before the result is ready
+    pub fn set_conversion_nacks(&mut self, nacks: u32) {
+        self.conversion_nacks = nacks;
+    }
+
+    /// true: send a wrong checksum byte with measurements and the electronic ID
+    pub fn set_crc_error(&mut self, crc_error: bool) {
+        self.crc_error = crc_error;
+    }
+
+    /// RH/T User Register 1 contents
+    pub fn user_register_1(&self) -> u8 {
+        self.user_register_1
+    }
+
+    /// Heater Control Register contents
+    pub fn heater_control(&self) -> u8 {
+        self.heater_control
+    }
+
+    /// Si7013 User Register 2 contents
+    pub fn user_register_2(&self) -> u8 {
+        self.user_register_2
+    }
+
+    /// Si7013 thermistor correction coefficient at address
+    pub fn thermistor_coefficient(&self, address: u8) -> u8 {
+        self.thermistor_coefficients[usize::from(address)]
+    }
+
+    // Si7013 commands are answered by the Si7013 and engineering samples, as the driver expects
+    fn is_si7013(&self) -> bool {
+        matches!(self.serial.snb().to_be_bytes()[0], 0x0d | 0x00 | 0xff)
+    }
+
+    fn crc(&self, data: &[u8]) -> u8 {
+        if self.crc_error { !crc8(data) } else { crc8(data) }
+    }
+
+    fn respond(&mut self, data: &[u8]) {
+        self.response[..data.len()].copy_from_slice(data);
+        self.response_len = data.len();
+    }
+
+    fn respond_code(&mut self, code: u16) {
+        let bytes = code.to_be_bytes();
+        let crc = self.crc(&bytes);
+        self.respond(&[bytes[0], bytes[1], crc]);
+    }
+
+    fn respond_id_1(&mut self) {
+        // SNA_3, CRC, SNA_2, CRC, SNA_1, CRC, SNA_0, CRC : each CRC covers all SNA bytes sent so far
+        let mut response = [0u8; 8];
+        let mut crc: u8 = 0x00;
+        for (pair, byte) in response.chunks_exact_mut(2).zip(self.serial.sna().to_be_bytes()) {
+            crc = crc8_update(crc, byte);
+            pair[0] = byte;
+            pair[1] = if self.crc_error { !crc } else { crc };
+        }
+        self.respond(&response);
+    }
+
+    fn respond_id_2(&mut self) {
+        // SNB_3, SNB_2, CRC, SNB_1, SNB_0, CRC : each CRC covers all SNB bytes sent so far
+        let snb = self.serial.snb().to_be_bytes();
+        let crc_1 = self.crc(&snb[0..2]);
+        let crc_2 = self.crc(&snb);
+        self.respond(&[snb[0], snb[1], crc_1, snb[2], snb[3], crc_2]);
+    }
+
+    fn reset(&mut self) {
+        self.user_register_1 = UserRegister1::RESET_VALUE | (self.user_register_1 & Self::VDDS);
+        self.heater_control = Self::HEATER_CONTROL_RESET;
+        self.user_register_2 = Self::USER_REGISTER_2_RESET;
+        self.nacks_left = 0;
+        self.response_len = 0;
+    }
+
+    // handle a command written to the device, unsupported commands are NACKed on their data
+    #[allow(non_upper_case_globals)]  // the command constants are matched as patterns
+    fn write(&mut self, bytes: &[u8]) -> Result<(), ErrorKind> {
+        let nack = Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data));
+        let si70xx = self.variant == SensorVariant::Si70xx;
+        let si7013 = si70xx && self.is_si7013();
+        match *bytes {
+            [] => {}
+            [Si7021_READ_RH_HOLD] | [Si7021_READ_RH_NO_HOLD] => {
+                self.previous_rh_temperature_code = self.temperature_code;
+                self.respond_code(self.humidity_code);
+                // a new command ends a conversion still NACKing, Hold Master Mode stretches the clock instead
+                self.nacks_left = if bytes[0] == Si7021_READ_RH_NO_HOLD { self.conversion_nacks } else { 0 };
+            }
+            [Si7021_READ_TEMP_HOLD] | [Si7021_READ_TEMP_NO_HOLD] => {
+                self.respond_code(self.temperature_code);
+                // a new command ends a conversion still NACKing, Hold Master Mode stretches the clock instead
+                self.nacks_left = if bytes[0] == Si7021_READ_TEMP_NO_HOLD { self.conversion_nacks } else { 0 };
+            }
+            [Si7021_READ_TEMP_AFTER_PREVIOUS_RH] if si70xx => {
+                self.respond(&self.previous_rh_temperature_code.to_be_bytes());
+            }
+            [Si7021_RESET] => self.reset(),
+            [Si7021_WRITE_RH_T_USER_REG_1, value] => {
+                // VDDS is read only
+                self.user_register_1 = (value & !Self::VDDS) | (self.user_register_1 & Self::VDDS);
+            }
+            [Si7021_READ_RH_T_USER_REG_1] => self.respond(&[self.user_register_1]),
+            [Si7021_WRITE_HEATER_CONTROL, value] if si70xx => self.heater_control = value & 0x0f,
+            [Si7021_READ_HEATER_CONTROL] if si70xx => self.respond(&[self.heater_control]),
+            [first, second] if [first, second] == Si7021_READ_ID_BYTE_1 => self.respond_id_1(),
+            [first, second] if [first, second] == Si7021_READ_ID_BYTE_2 => self.respond_id_2(),
+            [first, second] if si70xx && [first, second] == Si7021_READ_FW_VERSION => self.respond(&[self.firmware]),
+            [Si7013_MEASURE_ANALOG] if si7013 => {
+                self.respond_code(self.analog_code);
+                self.nacks_left = if (self.user_register_2 & Self::NO_HOLD) != 0x00 { self.conversion_nacks } else { 0 };
+            }
+            [Si7013_WRITE_USER_REG_2, value] if si7013 => self.user_register_2 = value,
+            [Si7013_READ_USER_REG_2] if si7013 => self.respond(&[self.user_register_2]),
+            [Si7013_WRITE_THERMISTOR_COEFF, address, value] if si7013 => {
+                self.thermistor_coefficients[usize::from(address)] = value;
+            }
+            [Si7013_READ_THERMISTOR_COEFF, address] if si7013 => {
+                self.respond(&[self.thermistor_coefficients[usize::from(address)]]);
+            }
+            _ => return nack,
+        }
+        Ok(())
+    }
+
+    // read the answer to the last command, the read address is NACKed while a No Hold Master Mode
+    // conversion is running or when there is nothing to read
+    fn read(&mut self, buffer: &mut [u8]) -> Result<(), ErrorKind> {
+        if self.nacks_left > 0 {
+            self.nacks_left -= 1;
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+        if self.response_len == 0 {
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+        for (i, byte) in buffer.iter_mut().enumerate() {
+            // the bus reads 0xff after the last byte sent by the device
+            *byte = if i < self.response_len { self.response[i] } else { 0xff };
+        }
+        self.response_len = 0;
+        Ok(())
+    }
+
+    fn run_transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), ErrorKind> {
+        if !self.connected || (address != self.address) {
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => self.write(bytes)?,
+                Operation::Read(buffer) => self.read(buffer)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for SimSi7021 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ErrorType for SimSi7021 {
+    type Error = ErrorKind;
+}
+
+impl embedded_hal::i2c::I2c for SimSi7021 {
+    fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        self.run_transaction(address, operations)
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::I2c for SimSi7021 {
+    async fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        self.run_transaction(address, operations)
+    }
+}
+
+/// delay provider for SimSi7021 that returns at once and adds up the requested delays
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimDelay {
+    elapsed_ns: u64,
+}
+
+impl SimDelay {
+    pub fn new() -> Self {
+        SimDelay { elapsed_ns: 0 }
+    }
+
+    /// sum of all requested delays in nanoseconds
+    pub fn elapsed_ns(&self) -> u64 {
+        self.elapsed_ns
+    }
+}
+
+impl embedded_hal::delay::DelayNs for SimDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.elapsed_ns += u64::from(ns);
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::delay::DelayNs for SimDelay {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.elapsed_ns += u64::from(ns);
+    }
+}
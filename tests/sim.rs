@@ -0,0 +1,395 @@
+// driver tests against the emulated Si7021 of the sim module, run with: cargo test --features sim
+
+use embedded_hal::i2c::ErrorKind;
+
+use si7021_t_rh::blocking::Si7021;
+use si7021_t_rh::constants::DeviceAddress;
+use si7021_t_rh::data::{DeviceModel, FirmwareRevision, HeaterLevel, MeasurementMode, Resolution, SensorVariant, SerialNumber};
+use si7021_t_rh::error::Error;
+use si7021_t_rh::sim::{SimDelay, SimSi7021};
+
+fn driver(sensor: &mut SimSi7021) -> Si7021<&mut SimSi7021, SimDelay> {
+    Si7021::new(sensor, SimDelay::new())
+}
+
+#[test]
+fn init_device_detects_si7021() {
+    let mut sensor = SimSi7021::new();
+    sensor.set_serial(SerialNumber::new(0x0102_0304, 0x1505_0607));
+    let mut si7021 = driver(&mut sensor);
+    si7021.init_device().unwrap();
+    assert_eq!(si7021.device_data.id, DeviceModel::Si7021);
+    assert_eq!(si7021.device_data.firmware_version, FirmwareRevision::V2_0);
+    assert_eq!(si7021.device_data.serial.as_u64(), 0x0102_0304_1505_0607);
+    assert_eq!(si7021.sensor_variant(), SensorVariant::Si70xx);
+
+    let detected = si7021.detect().unwrap();
+    assert_eq!(detected.id, DeviceModel::Si7021);
+    assert_eq!(detected.variant, SensorVariant::Si70xx);
+}
+
+#[test]
+fn measurements_read_back_the_sensor_values() {
+    let mut sensor = SimSi7021::new();
+    sensor.set_temperature_centi_c(-1234);
+    sensor.set_relative_humidity_milli_percent(67_000);
+    let mut si7021 = driver(&mut sensor);
+    si7021.init_device().unwrap();
+    assert_eq!(si7021.read_temperature_centi_c().unwrap(), -1234);
+    let measurements = si7021.read_measurements_int().unwrap();
+    assert_eq!(measurements.temperature_centi_c, -1234);
+    assert!((67_000..=67_002).contains(&measurements.relative_humidity_milli_percent));
+    assert!(!measurements.vdd_low);
+}
+
+#[test]
+fn crc_error_gives_crc_mismatch() {
+    let mut sensor = SimSi7021::new();
+    sensor.set_crc_error(true);
+    let mut si7021 = driver(&mut sensor);
+    assert!(matches!(si7021.init_device(), Err(Error::CrcMismatch { command: 0xfa, .. })));
+    assert!(matches!(si7021.read_raw_temperature(), Err(Error::CrcMismatch { command: 0xf3, .. })));
+
+    si7021.set_measurement_mode(MeasurementMode::Hold);
+    assert!(matches!(si7021.read_raw_humidity(), Err(Error::CrcMismatch { command: 0xe5, .. })));
+
+    si7021.set_crc_check(false);
+    assert!(si7021.read_raw_humidity().is_ok());
+    assert!(si7021.init_device().is_ok());
+}
+
+#[test]
+fn conversion_nacks_are_polled_until_the_timeout() {
+    let mut sensor = SimSi7021::new();
+    sensor.set_conversion_nacks(3);
+    let mut si7021 = driver(&mut sensor);
+    assert!(si7021.read_raw_temperature().is_ok());
+
+    let mut sensor = SimSi7021::new();
+    sensor.set_conversion_nacks(1_000);
+    let mut si7021 = driver(&mut sensor);
+    assert!(matches!(
+        si7021.read_raw_temperature(),
+        Err(Error::MeasurementTimeout { command: 0xf3, waited_us: 50_000 })
+    ));
+    si7021.set_measurement_timeout_us(10_000);
+    assert!(matches!(
+        si7021.read_raw_humidity(),
+        Err(Error::MeasurementTimeout { command: 0xf5, waited_us: 10_000 })
+    ));
+
+    // Hold Master Mode stretches the clock instead of NACKing
+    si7021.set_measurement_mode(MeasurementMode::Hold);
+    assert!(si7021.read_raw_temperature().is_ok());
+}
+
+#[test]
+fn poll_interval_sets_the_read_retries() {
+    let mut sensor = SimSi7021::new();
+    sensor.set_conversion_nacks(4);
+    let mut delay = SimDelay::new();
+    let mut si7021 = Si7021::new(&mut sensor, &mut delay);
+    si7021.set_poll_interval_us(2_500);
+    si7021.read_raw_temperature().unwrap();
+    si7021.release();
+    // 4 NACKed reads, the 5th returns the result
+    assert_eq!(delay.elapsed_ns(), 5 * 2_500_000);
+
+    sensor.set_conversion_nacks(1_000);
+    let mut delay = SimDelay::new();
+    let mut si7021 = Si7021::new(&mut sensor, &mut delay);
+    si7021.set_poll_interval_us(3_000);
+    // the 50 ms timeout is passed on the 17th poll
+    assert!(matches!(
+        si7021.read_raw_temperature(),
+        Err(Error::MeasurementTimeout { command: 0xf3, waited_us: 51_000 })
+    ));
+    si7021.release();
+    assert_eq!(delay.elapsed_ns(), 51_000_000);
+}
+
+#[test]
+fn missing_sensor_is_not_connected() {
+    let mut sensor = SimSi7021::new();
+    sensor.set_connected(false);
+    let mut si7021 = driver(&mut sensor);
+    assert!(matches!(si7021.is_connected(), Err(Error::NotConnected)));
+    assert!(matches!(si7021.init_device(), Err(Error::NotConnected)));
+    assert!(matches!(si7021.read_raw_humidity(), Err(Error::AddressNack { address: 0x40, command: 0xf5 })));
+    assert!(matches!(si7021.read_user_register(), Err(Error::AddressNack { address: 0x40, command: 0xe7 })));
+}
+
+#[test]
+fn sht21_compatible_sensor_rejects_si70xx_commands() {
+    let mut sensor = SimSi7021::new();
+    sensor.set_sensor_variant(SensorVariant::Sht21Compatible);
+    // HTU21D electronic ID: SNC_1 where the Si70xx sends its device ID byte
+    sensor.set_device_id(0x32);
+    sensor.set_temperature_centi_c(2000);
+    let mut si7021 = driver(&mut sensor);
+    si7021.init_device().unwrap();
+    assert_eq!(si7021.sensor_variant(), SensorVariant::Sht21Compatible);
+    assert_eq!(si7021.device_data.id, DeviceModel::NotRead);
+    assert_eq!(si7021.device_data.serial.snb() >> 24, 0x32);
+    assert_eq!(si7021.measurement_timeout_us(), 100_000);
+    assert!(matches!(si7021.get_heater_level(), Err(Error::Unsupported(0x11))));
+    assert!(matches!(si7021.set_heater_level(3), Err(Error::Unsupported(0x51))));
+    assert!(matches!(si7021.read_firmware_version(), Err(Error::Unsupported(0x84))));
+    assert!(matches!(si7021.read_device_model(), Err(Error::Unsupported(0xfa))));
+    // temperature is measured separately, the sensor NACKs Read Temperature Value from Previous RH Measurement
+    assert_eq!(si7021.read_measurements_int().unwrap().temperature_centi_c, 2000);
+}
+
+#[test]
+fn sht21_compatible_sensor_is_detected_by_the_firmware_revision() {
+    // an SNC_1 byte that happens to be a Si70xx device ID
+    let mut sensor = SimSi7021::new();
+    sensor.set_sensor_variant(SensorVariant::Sht21Compatible);
+    sensor.set_device_id(0x15);
+    let mut si7021 = driver(&mut sensor);
+    si7021.init_device().unwrap();
+    assert_eq!(si7021.sensor_variant(), SensorVariant::Sht21Compatible);
+    assert_eq!(si7021.device_data.id, DeviceModel::NotRead);
+    assert!(si7021.read_measurements_int().is_ok());
+
+    // an electronic ID checksum mismatch is only an error for a Si70xx
+    sensor.set_crc_error(true);
+    let mut si7021 = driver(&mut sensor);
+    let detected = si7021.detect().unwrap();
+    assert_eq!(detected.variant, SensorVariant::Sht21Compatible);
+}
+
+#[test]
+fn unknown_device_id_is_an_error() {
+    let mut sensor = SimSi7021::new();
+    sensor.set_device_id(0x33);
+    let mut si7021 = driver(&mut sensor);
+    assert!(matches!(si7021.init_device(), Err(Error::UnexpectedDeviceId(0x33))));
+    assert_eq!(si7021.device_data.id, DeviceModel::Unknown(0x33));
+    assert_eq!(si7021.sensor_variant(), SensorVariant::Si70xx);
+    assert!(si7021.set_heater_level(3).is_ok());
+}
+
+#[test]
+fn secondary_address_needs_si7013() {
+    let mut sensor = SimSi7021::new();
+    sensor.set_address(DeviceAddress::Secondary);
+    let mut si7021 = Si7021::new_with_address(&mut sensor, SimDelay::new(), DeviceAddress::Secondary);
+    assert!(matches!(si7021.init_device(), Err(Error::AddressNotSupported(0x41))));
+
+    let mut sensor = SimSi7021::new();
+    sensor.set_address(DeviceAddress::Secondary);
+    sensor.set_device_id(0x0d);
+    let mut si7021 = Si7021::new_with_address(&mut sensor, SimDelay::new(), DeviceAddress::Secondary);
+    si7021.init_device().unwrap();
+    assert_eq!(si7021.device_data.id, DeviceModel::Si7013);
+
+    // nothing answers at the primary address
+    let mut si7021 = Si7021::new(&mut sensor, SimDelay::new());
+    assert!(matches!(si7021.init_device(), Err(Error::NotConnected)));
+}
+
+#[test]
+fn resolution_round_trip() {
+    let mut sensor = SimSi7021::new();
+    let mut si7021 = driver(&mut sensor);
+    si7021.init_device().unwrap();
+    assert_eq!(si7021.get_resolution().unwrap(), Resolution::Rh12Temp14);
+    for resolution in [Resolution::Rh8Temp12, Resolution::Rh10Temp13, Resolution::Rh11Temp11, Resolution::Rh12Temp14] {
+        si7021.set_resolution(resolution).unwrap();
+        assert_eq!(si7021.get_resolution().unwrap(), resolution);
+        assert_eq!(si7021.resolution(), resolution);
+    }
+    si7021.set_resolution(Resolution::Rh11Temp11).unwrap();
+    si7021.release();
+    // reserved bits keep their reset value
+    assert_eq!(sensor.user_register_1(), 0x3a | 0x81);
+
+    let mut si7021 = driver(&mut sensor);
+    si7021.reset_device().unwrap();
+    assert_eq!(si7021.get_resolution().unwrap(), Resolution::Rh12Temp14);
+}
+
+#[test]
+fn conversion_time_follows_the_resolution_without_polling() {
+    for resolution in [Resolution::Rh12Temp14, Resolution::Rh8Temp12, Resolution::Rh10Temp13, Resolution::Rh11Temp11] {
+        let mut sensor = SimSi7021::new();
+        let mut delay = SimDelay::new();
+        let mut si7021 = Si7021::new(&mut sensor, &mut delay);
+        si7021.set_poll_interval_us(0);
+        si7021.set_resolution(resolution).unwrap();
+        si7021.read_raw_temperature().unwrap();
+        si7021.release();
+        let conversion_time_us = SensorVariant::Si70xx.temperature_conversion_time_us(resolution);
+        assert_eq!(delay.elapsed_ns(), u64::from(conversion_time_us) * 1000);
+
+        let mut delay = SimDelay::new();
+        let mut si7021 = Si7021::new(&mut sensor, &mut delay);
+        si7021.set_poll_interval_us(0);
+        si7021.set_resolution(resolution).unwrap();
+        si7021.read_raw_humidity().unwrap();
+        si7021.release();
+        let conversion_time_us = SensorVariant::Si70xx.humidity_conversion_time_us(resolution);
+        assert_eq!(delay.elapsed_ns(), u64::from(conversion_time_us) * 1000);
+    }
+}
+
+#[test]
+fn heater_level_and_enable() {
+    let mut sensor = SimSi7021::new();
+    let mut si7021 = driver(&mut sensor);
+    si7021.init_device().unwrap();
+    assert_eq!(si7021.get_heater_level().unwrap(), HeaterLevel::new(0).unwrap());
+    si7021.set_heater_level(9).unwrap();
+    assert_eq!(si7021.get_heater_level().unwrap().level(), 9);
+    assert!(matches!(si7021.set_heater_level(0x10), Err(Error::OutOfRange(0x10))));
+
+    assert!(!si7021.is_heater_enabled().unwrap());
+    si7021.heater_control(true).unwrap();
+    assert!(si7021.is_heater_enabled().unwrap());
+    si7021.release();
+    assert_eq!(sensor.heater_control(), 9);
+    assert_eq!(sensor.user_register_1(), 0x3a | 0x04);
+
+    // reset turns the heater off
+    let mut si7021 = driver(&mut sensor);
+    si7021.reset_device().unwrap();
+    assert!(!si7021.is_heater_enabled().unwrap());
+    assert_eq!(si7021.get_heater_level().unwrap().level(), 0);
+}
+
+#[test]
+fn vdd_low_flag() {
+    let mut sensor = SimSi7021::new();
+    let mut si7021 = driver(&mut sensor);
+    assert!(!si7021.is_vdd_low().unwrap());
+    si7021.release();
+
+    sensor.set_vdd_low(true);
+    let mut si7021 = driver(&mut sensor);
+    assert!(si7021.is_vdd_low().unwrap());
+    assert!(si7021.read_measurements_int().unwrap().vdd_low);
+    // VDDS is read only, writing User Register 1 keeps it
+    si7021.heater_control(true).unwrap();
+    assert!(si7021.is_vdd_low().unwrap());
+}
+
+#[test]
+fn non_blocking_fetch_follows_the_started_measurement() {
+    let mut sensor = SimSi7021::new();
+    sensor.set_conversion_nacks(2);
+    sensor.set_temperature_code(0x7000);
+    let mut si7021 = driver(&mut sensor);
+    assert!(matches!(si7021.try_fetch_raw(), Err(nb::Error::Other(Error::MeasurementNotStarted))));
+
+    si7021.start_temperature_measurement().unwrap();
+    assert!(matches!(si7021.try_fetch_raw(), Err(nb::Error::WouldBlock)));
+    assert!(matches!(si7021.try_fetch_raw(), Err(nb::Error::WouldBlock)));
+    assert_eq!(si7021.try_fetch_raw().unwrap(), 0x7000);
+    assert!(matches!(si7021.try_fetch_raw(), Err(nb::Error::Other(Error::MeasurementNotStarted))));
+
+    // another command ends the started conversion
+    si7021.start_temperature_measurement().unwrap();
+    si7021.read_raw_humidity().unwrap();
+    assert!(matches!(si7021.try_fetch_raw(), Err(nb::Error::Other(Error::MeasurementNotStarted))));
+    si7021.start_humidity_measurement().unwrap();
+    si7021.reset_device().unwrap();
+    assert!(matches!(si7021.try_fetch_raw(), Err(nb::Error::Other(Error::MeasurementNotStarted))));
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn non_blocking_fetch_checks_the_measurement_kind() {
+    let mut sensor = SimSi7021::new();
+    sensor.set_temperature_centi_c(3000);
+    let mut si7021 = driver(&mut sensor);
+    si7021.start_temperature_measurement().unwrap();
+    assert!(matches!(
+        si7021.try_fetch_humidity(),
+        Err(nb::Error::Other(Error::MeasurementMismatch { started: 0xf3, requested: 0xf5 }))
+    ));
+    let temperature = si7021.try_fetch_temperature().unwrap();
+    assert!((temperature - 30.0).abs() < 0.01);
+    assert!(matches!(si7021.try_fetch_temperature(), Err(nb::Error::Other(Error::MeasurementNotStarted))));
+}
+
+#[test]
+fn into_kind_keeps_the_context() {
+    let mut sensor = SimSi7021::new();
+    sensor.set_connected(false);
+    let mut si7021 = driver(&mut sensor);
+    let error: Error<ErrorKind> = si7021.read_raw_temperature().unwrap_err().into_kind();
+    assert!(matches!(error, Error::AddressNack { address: 0x40, command: 0xf3 }));
+}
+
+#[cfg(all(feature = "si7013", feature = "float"))]
+#[test]
+fn si7013_analog_input_and_thermistor() {
+    use si7021_t_rh::data::{ThermistorConfig, VoltageReference};
+
+    let mut sensor = SimSi7021::new();
+    sensor.set_device_id(0x0d);
+    sensor.set_analog_code(0x4000);
+    sensor.set_conversion_nacks(2);
+    let mut si7021 = driver(&mut sensor);
+    assert!(matches!(si7021.read_analog_raw(), Err(Error::NotInitialized)));
+    si7021.init_device().unwrap();
+    assert_eq!(si7021.read_analog_raw().unwrap(), 0x4000);
+    assert!((si7021.read_analog_voltage(1.25).unwrap() - 0.625).abs() < 0.0001);
+    assert!(si7021.read_user_register_2().unwrap().no_hold);
+    assert!(matches!(si7021.read_thermistor_temperature(), Err(Error::ThermistorNotConfigured)));
+
+    let config = ThermistorConfig { coefficients: &[(0x82, 0x12), (0x83, 0x34)], reference: VoltageReference::Vdda, vin_buffered: true };
+    si7021.configure_thermistor(&config).unwrap();
+    assert_eq!(si7021.read_thermistor_coefficient(0x83).unwrap(), 0x34);
+    assert!(si7021.read_thermistor_temperature().is_ok());
+
+    si7021.set_measurement_mode(MeasurementMode::Hold);
+    assert_eq!(si7021.read_analog_raw().unwrap(), 0x4000);
+    si7021.release();
+    // THERM_CORR, VIN_BUF, VREFP and VOUT set, NO_HOLD cleared for Hold Master Mode
+    assert_eq!(sensor.user_register_2(), 0x27);
+
+    let mut sensor = SimSi7021::new();
+    let mut si7021 = driver(&mut sensor);
+    si7021.init_device().unwrap();
+    assert!(matches!(si7021.read_analog_raw(), Err(Error::Unsupported(0xee))));
+}
+
+#[cfg(feature = "async")]
+mod asynch {
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+
+    use si7021_t_rh::asynch::Si7021;
+    use si7021_t_rh::data::DeviceModel;
+    use si7021_t_rh::error::Error;
+    use si7021_t_rh::sim::{SimDelay, SimSi7021};
+
+    // the emulated sensor and delay complete at once, so a single poll finishes any driver future
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        match future.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("driver future pending on the emulated sensor"),
+        }
+    }
+
+    #[test]
+    fn async_driver_reads_the_sensor() {
+        let mut sensor = SimSi7021::new();
+        sensor.set_temperature_centi_c(1850);
+        sensor.set_conversion_nacks(3);
+        let mut si7021 = Si7021::new(&mut sensor, SimDelay::new());
+        block_on(si7021.init_device()).unwrap();
+        assert_eq!(si7021.device_data.id, DeviceModel::Si7021);
+        assert_eq!(block_on(si7021.read_temperature_centi_c()).unwrap(), 1850);
+        si7021.set_measurement_timeout_us(2_000);
+        si7021.release();
+
+        sensor.set_conversion_nacks(1_000);
+        let mut si7021 = Si7021::new(&mut sensor, SimDelay::new());
+        assert!(matches!(block_on(si7021.read_raw_humidity()), Err(Error::MeasurementTimeout { command: 0xf5, .. })));
+    }
+}